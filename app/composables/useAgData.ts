@@ -5,8 +5,8 @@ import {
     type ValueFormatterParams,
 } from "ag-grid-community";
 import { Temporal } from "temporal-polyfill";
-import type { ShallowRef } from "vue";
-import type { EventType, HourType, TelemetryEvent } from "~/utils/api";
+import type { Ref, ShallowRef } from "vue";
+import type { EventType, HourTypeDetails, TelemetryEvent } from "~/utils/api";
 import api, { EventTypeTitles } from "~/utils/api";
 import { Math2 } from "~/utils/math";
 import type { Row } from "./useTable";
@@ -15,9 +15,9 @@ export type AgRow = {
     studentId: string;
     first: string;
     last: string;
-    [date: string]: any;
+    [column: string]: any;
     total: number;
-} & Record<HourType, number>;
+};
 
 function dateFmt(date: Temporal.PlainDate) {
     const now = Temporal.Now.plainDateISO();
@@ -53,8 +53,11 @@ function hourFormat(hours: ValueFormatterParams): string {
     return Math2.formatHours(hours.value as number);
 }
 
-export function useAgStudents(data: ShallowRef<Map<string, Row>>) {
-    const ag = computedWithControl(data, () => {
+export function useAgStudents(
+    data: ShallowRef<Map<string, Row>>,
+    kinds: Ref<HourTypeDetails[]>,
+) {
+    const ag = computedWithControl([data, kinds], () => {
         const students = data.value;
         if (students.size === 0) {
             return {
@@ -116,34 +119,13 @@ export function useAgStudents(data: ShallowRef<Map<string, Row>>) {
             {
                 headerName: "Totals",
                 children: [
-                    {
-                        field: "offseason",
-                        headerName: "Offseason",
-                        columnGroupShow: "open",
-                        pinned: "right",
+                    ...kinds.value.map((kind) => ({
+                        field: kind.id,
+                        headerName: kind.name,
+                        columnGroupShow: "open" as const,
+                        pinned: "right" as const,
                         valueFormatter: hourFormat,
-                    },
-                    {
-                        field: "build",
-                        headerName: "Build",
-                        columnGroupShow: "open",
-                        pinned: "right",
-                        valueFormatter: hourFormat,
-                    },
-                    {
-                        field: "learning",
-                        headerName: "Learning",
-                        columnGroupShow: "open",
-                        pinned: "right",
-                        valueFormatter: hourFormat,
-                    },
-                    {
-                        field: "demo",
-                        headerName: "Outreach",
-                        columnGroupShow: "open",
-                        pinned: "right",
-                        valueFormatter: hourFormat,
-                    },
+                    })),
                     {
                         field: "total",
                         headerName: "Total",
@@ -189,18 +171,16 @@ export function useAgStudents(data: ShallowRef<Map<string, Row>>) {
                         if (!entry.sign_out) continue;
                         const diff = entry.sign_out.since(entry.sign_in);
                         const hours = diff.total("hours");
-                        acc[entry.hour_type] += hours;
+                        acc[entry.hour_type] =
+                            (acc[entry.hour_type] ?? 0) + hours;
                         acc.total += hours;
                     }
                     return acc;
                 },
                 {
-                    offseason: 0,
-                    build: 0,
-                    learning: 0,
-                    demo: 0,
+                    ...Object.fromEntries(kinds.value.map((k) => [k.id, 0])),
                     total: 0,
-                },
+                } as Record<string, number>,
             ),
         }));
 
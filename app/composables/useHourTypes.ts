@@ -0,0 +1,45 @@
+import type { HourType, HourTypeDetails } from "~/utils/api";
+import api from "~/utils/api";
+
+let pending: Promise<unknown> | null = null;
+
+export function useHourTypes() {
+    const types = useState("hour-types", () => [] as HourTypeDetails[]);
+
+    async function fetch(): Promise<unknown> {
+        const res = await api.hour_type.query_many();
+        if (!res.data) return api.error(res.error, res.response);
+
+        types.value = res.data;
+    }
+
+    if (types.value.length === 0) {
+        pending ??= fetch().finally(() => (pending = null));
+    }
+
+    const ids = computed(() => types.value.map((t) => t.id));
+    const names = computed(() => {
+        const names = {} as Record<HourType, string>;
+
+        for (const t of types.value) {
+            names[t.id] = t.name;
+        }
+
+        return names;
+    });
+
+    return {
+        types,
+        ids,
+        names,
+        ready: pending ?? Promise.resolve(),
+        reload: fetch,
+    };
+}
+
+export function hourTypeName(
+    names: Record<HourType, string>,
+    kind: HourType,
+) {
+    return names[kind] ?? kind;
+}
@@ -1,4 +1,4 @@
-import type { HourType, Permissions, TelemetryEvent } from "./hey/types.gen";
+import type { Permissions, TelemetryEvent } from "./hey/types.gen";
 
 export const PermissionTitles: Record<Permission, string> = {
     student_add: "Add Students",
@@ -15,20 +15,6 @@ export const PermissionTitles: Record<Permission, string> = {
     admin_edit: "Edit Admins",
 };
 
-export const HourTypeLabels: Record<HourType, string> = {
-    build: "Build",
-    learning: "Learning",
-    demo: "Outreach",
-    offseason: "Offseason",
-};
-
-export const HourTypeTitles: Record<HourType, string> = {
-    build: "Build Season",
-    learning: "Learning Days",
-    demo: "Outreach Hours",
-    offseason: "Offseason",
-};
-
 export const EventTypeTitles = {
     invite_add: "New Invite",
     invite_use: "Invite Used",
@@ -48,4 +34,3 @@ export const EventTypeTitles = {
 
 export type EventType = keyof typeof EventTypeTitles;
 export type Permission = keyof Permissions;
-export const HourTypes = Object.keys(HourTypeLabels) as FixedArray<HourType, 4>;
@@ -3,7 +3,7 @@ import type { InputProps } from "~/components/ui/form/Input.vue";
 import type { OTPFieldProps } from "~/components/ui/form/otp/Field.vue";
 import type { SelectProps } from "~/components/ui/form/Select.vue";
 import type { HourType } from "../api";
-import api, { HourTypeLabels } from "../api";
+import api from "../api";
 import type { ButtonContext, FormButton } from "./button";
 import type { Deps } from "./deps";
 import {
@@ -119,10 +119,13 @@ export const f = {
 
     // project-specific items
     hourtype: {
-        any: (
+        async any(
             props: Omit<SelectProps<keyof HourType>, "kv"> & ItemProps = {},
-        ) => {
-            return f.select<Record<HourType, string>>(HourTypeLabels, {
+        ) {
+            const { names, ready } = useHourTypes();
+            await ready;
+
+            return f.select<Record<HourType, string>>(names.value, {
                 title: "Hour Type",
                 ...props,
             });
@@ -130,11 +133,11 @@ export const f = {
         async available(
             props: Omit<SelectProps<keyof HourType>, "kv"> & ItemProps = {},
         ) {
+            const ht = await f.hourtype.any();
             const available = await api.roster.allowed();
-            const ht = f.hourtype.any();
             const allowed = available.data ?? ht.zod.options;
             const titles = Object.fromEntries(
-                allowed.map((k) => [k, ht.props.kv[k]] as const),
+                allowed.map((k) => [k, ht.props.kv[k] ?? k] as const),
             );
 
             return f.select<Partial<Record<HourType, string>>>(titles, {
@@ -0,0 +1,42 @@
+-- Add migration script here
+CREATE TYPE auto_close_policy AS ENUM ('cutoff', 'credit', 'flag');
+
+CREATE TABLE IF NOT EXISTS auto_close_config (
+    kind hour_type PRIMARY KEY NOT NULL,
+    policy auto_close_policy NOT NULL DEFAULT 'cutoff',
+    cutoff time NOT NULL DEFAULT '21:30',
+    credit double precision NOT NULL DEFAULT 0 CHECK (credit >= 0)
+);
+
+INSERT INTO auto_close_config (kind, policy, cutoff, credit) VALUES
+    ('build', 'cutoff', '21:30', 0),
+    ('learning', 'cutoff', '21:30', 0),
+    ('demo', 'flag', '21:30', 0),
+    ('offseason', 'cutoff', '21:30', 0);
+
+ALTER TABLE records
+ADD COLUMN flagged BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TYPE event_type RENAME TO event_type_old;
+CREATE TYPE event_type AS ENUM (
+    'admin_login',
+    'admin_delete',
+    'admin_edit',
+    'permission_edit',
+    'invite_add',
+    'invite_use',
+    'student_add',
+    'student_delete',
+    'student_edit',
+    'record_add',
+    'record_delete',
+    'record_edit',
+    'record_auto_close',
+    'student_login',
+    'student_logout'
+);
+
+ALTER TABLE telemetry
+ALTER COLUMN event TYPE event_type USING event::text::event_type;
+
+DROP TYPE event_type_old;
@@ -0,0 +1,17 @@
+-- Add migration script here
+CREATE TYPE session_rounding AS ENUM ('nearest', 'floor', 'ceil');
+
+CREATE TABLE IF NOT EXISTS session_policy (
+    kind hour_type PRIMARY KEY NOT NULL,
+    min_minutes integer NOT NULL DEFAULT 0 CHECK (min_minutes >= 0),
+    max_minutes integer CHECK (max_minutes IS NULL OR max_minutes >= min_minutes),
+    rounding session_rounding NOT NULL DEFAULT 'floor',
+    increment_minutes integer NOT NULL DEFAULT 1 CHECK (increment_minutes > 0),
+    debounce_minutes integer NOT NULL DEFAULT 3 CHECK (debounce_minutes >= 0)
+);
+
+INSERT INTO session_policy (kind) VALUES
+    ('build'),
+    ('learning'),
+    ('demo'),
+    ('offseason');
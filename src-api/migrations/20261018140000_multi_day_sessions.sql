@@ -0,0 +1,7 @@
+-- Add migration script here
+ALTER TABLE session_policy
+ADD COLUMN multi_day boolean NOT NULL DEFAULT false,
+ADD COLUMN max_span_hours integer NOT NULL DEFAULT 72 CHECK (max_span_hours > 0);
+
+ALTER TABLE records
+ADD COLUMN multi_day BOOLEAN NOT NULL DEFAULT FALSE;
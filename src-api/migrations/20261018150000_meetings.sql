@@ -0,0 +1,12 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS meetings (
+    id TEXT PRIMARY KEY NOT NULL,
+    date date NOT NULL,
+    starts time NOT NULL,
+    ends time NOT NULL,
+    hour_type hour_type NOT NULL,
+    mandatory boolean NOT NULL DEFAULT false,
+    CHECK (starts < ends)
+);
+
+CREATE INDEX IF NOT EXISTS meetings_date ON meetings (date);
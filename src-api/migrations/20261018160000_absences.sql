@@ -0,0 +1,51 @@
+-- Add migration script here
+CREATE TYPE absence_reason AS ENUM ('illness', 'school_event', 'family', 'other');
+
+CREATE TABLE IF NOT EXISTS absences (
+    id TEXT PRIMARY KEY NOT NULL,
+    sid_hashed TEXT NOT NULL REFERENCES students(id_hashed) ON DELETE CASCADE,
+    -- either a single meeting, or an inclusive range of dates
+    meeting_id TEXT REFERENCES meetings(id) ON DELETE CASCADE,
+    starts date,
+    ends date,
+    reason absence_reason NOT NULL,
+    note TEXT,
+    created_by TEXT REFERENCES admins(id) ON DELETE SET NULL,
+    approved_by TEXT REFERENCES admins(id) ON DELETE SET NULL,
+    approved_at TIMESTAMPTZ, -- null until approved
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (
+        (meeting_id IS NOT NULL AND starts IS NULL AND ends IS NULL)
+        OR (meeting_id IS NULL AND starts IS NOT NULL AND ends IS NOT NULL AND starts <= ends)
+    )
+);
+
+ALTER TABLE permissions
+ADD COLUMN absence_approve BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TYPE event_type RENAME TO event_type_old;
+CREATE TYPE event_type AS ENUM (
+    'admin_login',
+    'admin_delete',
+    'admin_edit',
+    'permission_edit',
+    'invite_add',
+    'invite_use',
+    'student_add',
+    'student_delete',
+    'student_edit',
+    'record_add',
+    'record_delete',
+    'record_edit',
+    'record_auto_close',
+    'student_login',
+    'student_logout',
+    'absence_add',
+    'absence_approve',
+    'absence_delete'
+);
+
+ALTER TABLE telemetry
+ALTER COLUMN event TYPE event_type USING event::text::event_type;
+
+DROP TYPE event_type_old;
@@ -0,0 +1,23 @@
+-- Add migration script here
+CREATE TYPE geofence_policy AS ENUM ('reject', 'flag', 'ignore');
+
+CREATE TABLE IF NOT EXISTS geofence_config (
+    kind hour_type PRIMARY KEY NOT NULL,
+    policy geofence_policy NOT NULL DEFAULT 'ignore'
+);
+
+INSERT INTO geofence_config (kind) VALUES
+    ('build'),
+    ('learning'),
+    ('demo'),
+    ('offseason');
+
+CREATE TABLE IF NOT EXISTS geofences (
+    id TEXT PRIMARY KEY NOT NULL,
+    hour_type hour_type NOT NULL,
+    latitude double precision NOT NULL CHECK (latitude BETWEEN -90 AND 90),
+    longitude double precision NOT NULL CHECK (longitude BETWEEN -180 AND 180),
+    radius double precision NOT NULL CHECK (radius > 0) -- meters
+);
+
+CREATE INDEX geofences_hour_type_idx ON geofences (hour_type);
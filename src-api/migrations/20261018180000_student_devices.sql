@@ -0,0 +1,13 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS student_devices (
+    id TEXT PRIMARY KEY NOT NULL,
+    sid_hashed TEXT NOT NULL REFERENCES students(id_hashed) ON DELETE CASCADE,
+    token_hash TEXT NOT NULL UNIQUE, -- hex-encoded SHA-256 of the device token
+    name TEXT,
+    enrolled_by TEXT REFERENCES admins(id) ON DELETE SET NULL,
+    enrolled_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ -- null until revoked
+);
+
+CREATE INDEX student_devices_sid_hashed_idx ON student_devices (sid_hashed);
@@ -0,0 +1,13 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS badges (
+    id TEXT PRIMARY KEY NOT NULL,
+    uid_hashed TEXT NOT NULL,
+    sid_hashed TEXT NOT NULL REFERENCES students(id_hashed) ON DELETE CASCADE,
+    enrolled_by TEXT REFERENCES admins(id) ON DELETE SET NULL,
+    enrolled_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMPTZ -- null until revoked
+);
+
+-- a badge may be re-enrolled once revoked
+CREATE UNIQUE INDEX badges_uid_hashed_active_idx ON badges (uid_hashed) WHERE revoked_at IS NULL;
+CREATE INDEX badges_sid_hashed_idx ON badges (sid_hashed);
@@ -0,0 +1,42 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS hour_adjustments (
+    id TEXT PRIMARY KEY NOT NULL,
+    sid_hashed TEXT NOT NULL REFERENCES students(id_hashed) ON DELETE CASCADE,
+    hour_type hour_type NOT NULL,
+    hours double precision NOT NULL CHECK (hours <> 0), -- negative to deduct
+    date date NOT NULL,
+    reason TEXT NOT NULL,
+    admin_id TEXT REFERENCES admins(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX hour_adjustments_sid_hashed_idx ON hour_adjustments (sid_hashed);
+
+ALTER TYPE event_type RENAME TO event_type_old;
+CREATE TYPE event_type AS ENUM (
+    'admin_login',
+    'admin_delete',
+    'admin_edit',
+    'permission_edit',
+    'invite_add',
+    'invite_use',
+    'student_add',
+    'student_delete',
+    'student_edit',
+    'record_add',
+    'record_delete',
+    'record_edit',
+    'record_auto_close',
+    'student_login',
+    'student_logout',
+    'absence_add',
+    'absence_approve',
+    'absence_delete',
+    'adjustment_add',
+    'adjustment_delete'
+);
+
+ALTER TABLE telemetry
+ALTER COLUMN event TYPE event_type USING event::text::event_type;
+
+DROP TYPE event_type_old;
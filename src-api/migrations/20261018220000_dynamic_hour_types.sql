@@ -0,0 +1,64 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS hour_types (
+    id TEXT PRIMARY KEY NOT NULL CHECK (id ~ '^[a-z0-9_-]+$'),
+    name TEXT NOT NULL,
+    color TEXT NOT NULL DEFAULT '#808080' CHECK (color ~ '^#[0-9a-fA-F]{6}$'),
+    begins date, -- year is ignored
+    ends date, -- year is ignored
+    goal double precision NOT NULL DEFAULT 0 CHECK (goal >= 0)
+);
+
+INSERT INTO hour_types (id, name, color, begins, ends, goal)
+SELECT
+    kind::text,
+    initcap(kind::text),
+    CASE kind
+        WHEN 'build' THEN '#ef4444'
+        WHEN 'learning' THEN '#3b82f6'
+        WHEN 'demo' THEN '#22c55e'
+        ELSE '#a855f7'
+    END,
+    begins,
+    ends,
+    goal
+FROM hour_config;
+
+INSERT INTO hour_types (id, name)
+SELECT kind::text, initcap(kind::text)
+FROM unnest(enum_range(NULL::hour_type)) AS kind
+ON CONFLICT (id) DO NOTHING;
+
+DROP TABLE hour_config;
+
+-- rows of these reference an hour type, which can't be deleted while in use
+ALTER TABLE records ALTER COLUMN hour_type DROP DEFAULT;
+ALTER TABLE records ALTER COLUMN hour_type TYPE TEXT USING hour_type::text;
+ALTER TABLE records ADD FOREIGN KEY (hour_type) REFERENCES hour_types(id);
+
+ALTER TABLE otps ALTER COLUMN hour_type TYPE TEXT USING hour_type::text;
+ALTER TABLE otps ADD FOREIGN KEY (hour_type) REFERENCES hour_types(id);
+
+ALTER TABLE meetings ALTER COLUMN hour_type TYPE TEXT USING hour_type::text;
+ALTER TABLE meetings ADD FOREIGN KEY (hour_type) REFERENCES hour_types(id);
+
+ALTER TABLE hour_adjustments ALTER COLUMN hour_type TYPE TEXT USING hour_type::text;
+ALTER TABLE hour_adjustments ADD FOREIGN KEY (hour_type) REFERENCES hour_types(id);
+
+-- configuration goes away with its hour type
+ALTER TABLE auto_close_config ALTER COLUMN kind TYPE TEXT USING kind::text;
+ALTER TABLE auto_close_config
+ADD FOREIGN KEY (kind) REFERENCES hour_types(id) ON DELETE CASCADE;
+
+ALTER TABLE session_policy ALTER COLUMN kind TYPE TEXT USING kind::text;
+ALTER TABLE session_policy
+ADD FOREIGN KEY (kind) REFERENCES hour_types(id) ON DELETE CASCADE;
+
+ALTER TABLE geofence_config ALTER COLUMN kind TYPE TEXT USING kind::text;
+ALTER TABLE geofence_config
+ADD FOREIGN KEY (kind) REFERENCES hour_types(id) ON DELETE CASCADE;
+
+ALTER TABLE geofences ALTER COLUMN hour_type TYPE TEXT USING hour_type::text;
+ALTER TABLE geofences
+ADD FOREIGN KEY (hour_type) REFERENCES hour_types(id) ON DELETE CASCADE;
+
+DROP TYPE hour_type;
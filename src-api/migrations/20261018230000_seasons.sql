@@ -0,0 +1,69 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS seasons (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    starts DATE NOT NULL,
+    ends DATE, -- null while the season is ongoing
+    archived_at TIMESTAMPTZ, -- set once totals have been frozen by a rollover
+    CHECK (ends IS NULL OR ends >= starts),
+    CHECK (archived_at IS NULL OR ends IS NOT NULL),
+    EXCLUDE USING gist (daterange(starts, ends, '[]') WITH &&)
+);
+
+-- totals as of rollover, so later edits to old records don't change history
+CREATE TABLE IF NOT EXISTS season_totals (
+    season_id TEXT NOT NULL REFERENCES seasons(id) ON DELETE CASCADE,
+    sid_hashed TEXT NOT NULL REFERENCES students(id_hashed) ON DELETE CASCADE,
+    hour_type TEXT NOT NULL REFERENCES hour_types(id) ON DELETE CASCADE,
+    hours double precision NOT NULL,
+    PRIMARY KEY (season_id, sid_hashed, hour_type)
+);
+
+CREATE TABLE IF NOT EXISTS season_goals (
+    season_id TEXT NOT NULL REFERENCES seasons(id) ON DELETE CASCADE,
+    hour_type TEXT NOT NULL REFERENCES hour_types(id) ON DELETE CASCADE,
+    goal double precision NOT NULL,
+    PRIMARY KEY (season_id, hour_type)
+);
+
+-- everything so far belongs to a single ongoing season
+INSERT INTO seasons (id, name, starts)
+SELECT
+    'initial',
+    'Current Season',
+    LEAST(
+        CURRENT_DATE,
+        (SELECT MIN(sign_in)::date FROM records),
+        (SELECT MIN(date) FROM hour_adjustments)
+    );
+
+ALTER TYPE event_type RENAME TO event_type_old;
+CREATE TYPE event_type AS ENUM (
+    'admin_login',
+    'admin_delete',
+    'admin_edit',
+    'permission_edit',
+    'invite_add',
+    'invite_use',
+    'student_add',
+    'student_delete',
+    'student_edit',
+    'record_add',
+    'record_delete',
+    'record_edit',
+    'record_auto_close',
+    'student_login',
+    'student_logout',
+    'absence_add',
+    'absence_approve',
+    'absence_delete',
+    'adjustment_add',
+    'adjustment_delete',
+    'hour_type_switch',
+    'season_rollover'
+);
+
+ALTER TABLE telemetry
+ALTER COLUMN event TYPE event_type USING event::text::event_type;
+
+DROP TYPE event_type_old;
@@ -0,0 +1,10 @@
+-- Add migration script here
+-- unset dates fall back to the kickoff heuristic and the usual defaults
+CREATE TABLE IF NOT EXISTS season_calendars (
+    year INTEGER PRIMARY KEY NOT NULL,
+    kickoff DATE CHECK (EXTRACT(YEAR FROM kickoff) = year),
+    build_ends DATE CHECK (EXTRACT(YEAR FROM build_ends) = year),
+    learning_starts DATE CHECK (EXTRACT(YEAR FROM learning_starts) = year),
+    offseason_starts DATE CHECK (EXTRACT(YEAR FROM offseason_starts) = year),
+    offseason_ends DATE CHECK (EXTRACT(YEAR FROM offseason_ends) = year)
+);
@@ -0,0 +1,23 @@
+-- Add migration script here
+-- hour types without any windows can be swiped at any time of day
+CREATE TABLE IF NOT EXISTS swipe_windows (
+    id TEXT PRIMARY KEY NOT NULL,
+    hour_type TEXT NOT NULL REFERENCES hour_types(id) ON DELETE CASCADE,
+    weekday SMALLINT NOT NULL CHECK (weekday BETWEEN 1 AND 7), -- ISO, monday is 1
+    starts TIME NOT NULL,
+    ends TIME NOT NULL,
+    CHECK (ends > starts)
+);
+
+CREATE INDEX swipe_windows_hour_type_idx ON swipe_windows (hour_type);
+
+CREATE TABLE IF NOT EXISTS blackouts (
+    id TEXT PRIMARY KEY NOT NULL,
+    date DATE NOT NULL,
+    hour_type TEXT REFERENCES hour_types(id) ON DELETE CASCADE, -- null blocks every hour type
+    reason TEXT,
+    created_by TEXT REFERENCES admins(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX blackouts_date_idx ON blackouts (date);
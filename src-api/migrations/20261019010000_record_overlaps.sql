@@ -0,0 +1,7 @@
+-- Add migration script here
+CREATE TYPE overlap_policy AS ENUM ('reject', 'flag', 'allow');
+
+ALTER TABLE session_policy
+ADD COLUMN overlap overlap_policy NOT NULL DEFAULT 'reject';
+
+CREATE INDEX IF NOT EXISTS records_sid_hashed_hour_type_idx ON records (sid_hashed, hour_type);
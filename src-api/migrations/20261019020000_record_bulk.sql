@@ -0,0 +1,32 @@
+-- Add migration script here
+ALTER TYPE event_type RENAME TO event_type_old;
+CREATE TYPE event_type AS ENUM (
+    'admin_login',
+    'admin_delete',
+    'admin_edit',
+    'permission_edit',
+    'invite_add',
+    'invite_use',
+    'student_add',
+    'student_delete',
+    'student_edit',
+    'record_add',
+    'record_delete',
+    'record_edit',
+    'record_bulk',
+    'record_auto_close',
+    'student_login',
+    'student_logout',
+    'absence_add',
+    'absence_approve',
+    'absence_delete',
+    'adjustment_add',
+    'adjustment_delete',
+    'hour_type_switch',
+    'season_rollover'
+);
+
+ALTER TABLE telemetry
+ALTER COLUMN event TYPE event_type USING event::text::event_type;
+
+DROP TYPE event_type_old;
@@ -0,0 +1,47 @@
+-- Add migration script here
+CREATE TYPE trash_kind AS ENUM ('record', 'student');
+
+CREATE TABLE IF NOT EXISTS trash (
+    kind trash_kind NOT NULL,
+    pkey TEXT NOT NULL, -- primary key of the deleted row
+    data JSONB NOT NULL, -- the deleted row, column for column
+    dependents JSONB NOT NULL DEFAULT '{}', -- rows deleted along with it, by table
+    deleted_by TEXT REFERENCES admins(id) ON DELETE SET NULL,
+    deleted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (kind, pkey)
+);
+
+CREATE INDEX trash_deleted_at_idx ON trash (deleted_at);
+
+ALTER TYPE event_type RENAME TO event_type_old;
+CREATE TYPE event_type AS ENUM (
+    'admin_login',
+    'admin_delete',
+    'admin_edit',
+    'permission_edit',
+    'invite_add',
+    'invite_use',
+    'student_add',
+    'student_delete',
+    'student_edit',
+    'record_add',
+    'record_delete',
+    'record_edit',
+    'record_bulk',
+    'record_auto_close',
+    'student_login',
+    'student_logout',
+    'absence_add',
+    'absence_approve',
+    'absence_delete',
+    'adjustment_add',
+    'adjustment_delete',
+    'hour_type_switch',
+    'season_rollover',
+    'trash_restore'
+);
+
+ALTER TABLE telemetry
+ALTER COLUMN event TYPE event_type USING event::text::event_type;
+
+DROP TYPE event_type_old;
@@ -0,0 +1,21 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS kiosks (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE, -- hex-encoded SHA-256 of the kiosk token
+    registered_by TEXT REFERENCES admins(id) ON DELETE SET NULL, -- swipes are made on their behalf
+    registered_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ -- null until revoked
+);
+
+CREATE TABLE IF NOT EXISTS kiosk_otps (
+    kiosk_id TEXT NOT NULL REFERENCES kiosks(id) ON DELETE CASCADE,
+    hour_type TEXT NOT NULL REFERENCES hour_types(id) ON DELETE CASCADE,
+    secret BYTEA NOT NULL,
+    PRIMARY KEY (kiosk_id, hour_type)
+);
+
+-- an admin's secret for one hour type no longer replaces their secret for another
+ALTER TABLE otps DROP CONSTRAINT otps_pkey;
+ALTER TABLE otps ADD PRIMARY KEY (admin_id, hour_type);
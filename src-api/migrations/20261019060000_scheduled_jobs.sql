@@ -0,0 +1,9 @@
+-- Add migration script here
+CREATE TABLE scheduled_jobs (
+    name TEXT PRIMARY KEY,
+    interval_seconds INTEGER NOT NULL CHECK (interval_seconds > 0),
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    last_started_at TIMESTAMPTZ,
+    last_finished_at TIMESTAMPTZ,
+    last_error TEXT
+);
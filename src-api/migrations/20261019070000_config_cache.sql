@@ -0,0 +1,49 @@
+-- Add migration script here
+CREATE OR REPLACE FUNCTION notify_config()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('invalidate:config', TG_TABLE_NAME);
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER invalidate_hour_types
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON hour_types
+FOR EACH STATEMENT
+EXECUTE FUNCTION notify_config();
+
+CREATE TRIGGER invalidate_season_calendars
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON season_calendars
+FOR EACH STATEMENT
+EXECUTE FUNCTION notify_config();
+
+CREATE TRIGGER invalidate_blackouts
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON blackouts
+FOR EACH STATEMENT
+EXECUTE FUNCTION notify_config();
+
+CREATE TRIGGER invalidate_swipe_windows
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON swipe_windows
+FOR EACH STATEMENT
+EXECUTE FUNCTION notify_config();
+
+CREATE TRIGGER invalidate_session_policy
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON session_policy
+FOR EACH STATEMENT
+EXECUTE FUNCTION notify_config();
+
+CREATE TRIGGER invalidate_geofence_config
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON geofence_config
+FOR EACH STATEMENT
+EXECUTE FUNCTION notify_config();
+
+CREATE TRIGGER invalidate_geofences
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON geofences
+FOR EACH STATEMENT
+EXECUTE FUNCTION notify_config();
+
+CREATE TRIGGER invalidate_student_id_config
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON student_id_config
+FOR EACH STATEMENT
+EXECUTE FUNCTION notify_config();
@@ -0,0 +1,3 @@
+-- Add migration script here
+-- season totals only read records signed in during the season
+CREATE INDEX IF NOT EXISTS records_sign_in_idx ON records (sign_in);
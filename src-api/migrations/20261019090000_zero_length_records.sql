@@ -0,0 +1,5 @@
+-- Add migration script here
+-- stale records with nothing to credit are auto-closed at their sign-in time
+ALTER TABLE records
+DROP CONSTRAINT time_check,
+ADD CONSTRAINT time_check CHECK (sign_out IS NULL OR sign_in <= sign_out);
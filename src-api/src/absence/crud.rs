@@ -0,0 +1,334 @@
+use chrono::NaiveDate;
+
+use crate::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Enum, sqlx::Type)]
+#[oai(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "absence_reason", rename_all = "snake_case")]
+pub(crate) enum AbsenceReason {
+    Illness,
+    SchoolEvent,
+    Family,
+    Other,
+}
+
+/// An excused absence, either from a single meeting or from every meeting in
+/// a range of dates
+#[derive(Clone, Debug, Serialize, Deserialize, Object)]
+pub(crate) struct Absence {
+    pub id: String,
+    pub sid_hashed: String,
+    /// Set if this absence covers a single meeting
+    pub meeting_id: Option<String>,
+    /// Set if this absence covers a range of dates, inclusive
+    pub starts: Option<NaiveDate>,
+    /// Set if this absence covers a range of dates, inclusive
+    pub ends: Option<NaiveDate>,
+    pub reason: AbsenceReason,
+    pub note: Option<String>,
+    pub created_by: Option<String>,
+    pub approved_by: Option<String>,
+    /// Null until approved. Only approved absences are excused.
+    pub approved_at: Option<chrono::DateTime<Utc>>,
+    pub created_at: chrono::DateTime<Utc>,
+}
+
+#[derive(Object, Debug)]
+#[oai(rename = "AbsenceCreateRequest")]
+pub(super) struct CreateRequest {
+    sid_hashed: String,
+    /// Mutually exclusive with `starts` and `ends`
+    meeting_id: Option<String>,
+    /// Mutually exclusive with `meeting_id`
+    starts: Option<NaiveDate>,
+    /// Mutually exclusive with `meeting_id`, must not be before `starts`
+    ends: Option<NaiveDate>,
+    reason: AbsenceReason,
+    note: Option<String>,
+}
+
+#[derive(Object)]
+#[oai(rename = "AbsenceQueryManyResponse")]
+pub(super) struct QueryManyResponse {
+    absences: Vec<Absence>,
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum QueryManyError {
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum CreateError {
+    /// Exactly one of `meeting_id` or `starts` and `ends` must be given, and
+    /// `ends` must not be before `starts`
+    #[oai(status = 400)]
+    #[construct(range, "Give either a meeting, or a start and end date in order")]
+    BadRequest(PlainText<String>),
+
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    /// No student or meeting with the given ID exists
+    #[oai(status = 404)]
+    #[construct(student, "Student not found")]
+    #[construct(meeting, "Meeting not found")]
+    NotFound(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum Error {
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    /// Absence with the given ID does not exist
+    #[oai(status = 404)]
+    #[construct("Absence not found")]
+    NotFound(PlainText<String>),
+
+    #[oai(status = 409)]
+    #[construct("Absence already approved")]
+    Conflict(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn query_many(
+    sid_hashed: Option<String>,
+    pending: bool,
+    pg: PgPool,
+) -> Result<QueryManyResponse, QueryManyError> {
+    let absences = sqlx::query_as!(
+        Absence,
+        r#"
+        SELECT
+            id,
+            sid_hashed,
+            meeting_id,
+            starts,
+            ends,
+            reason AS "reason: AbsenceReason",
+            note,
+            created_by,
+            approved_by,
+            approved_at,
+            created_at
+        FROM absences
+        WHERE ($1::text IS NULL OR sid_hashed = $1)
+            AND (NOT $2 OR approved_at IS NULL)
+        ORDER BY created_at DESC
+        "#,
+        sid_hashed,
+        pending,
+    )
+    .fetch_all(&pg)
+    .await?;
+
+    Ok(QueryManyResponse { absences })
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn add(
+    CreateRequest {
+        sid_hashed,
+        meeting_id,
+        starts,
+        ends,
+        reason,
+        note,
+    }: CreateRequest,
+    claims: jwt::Claims,
+    pg: PgPool,
+) -> Result<Absence, CreateError> {
+    match (&meeting_id, starts, ends) {
+        (Some(_), None, None) => {}
+        (None, Some(starts), Some(ends)) if starts <= ends => {}
+        _ => return Err(CreateError::range()),
+    }
+
+    sqlx::query!(
+        r#"SELECT 1 AS "exists" FROM students WHERE id_hashed = $1"#,
+        sid_hashed
+    )
+    .fetch_optional(&pg)
+    .await?
+    .ok_or(CreateError::student())?;
+
+    if let Some(meeting_id) = &meeting_id {
+        sqlx::query!(
+            r#"SELECT 1 AS "exists" FROM meetings WHERE id = $1"#,
+            meeting_id
+        )
+        .fetch_optional(&pg)
+        .await?
+        .ok_or(CreateError::meeting())?;
+    }
+
+    let absence = sqlx::query_as!(
+        Absence,
+        r#"
+        INSERT INTO absences (id, sid_hashed, meeting_id, starts, ends, reason, note, created_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING
+            id,
+            sid_hashed,
+            meeting_id,
+            starts,
+            ends,
+            reason AS "reason: AbsenceReason",
+            note,
+            created_by,
+            approved_by,
+            approved_at,
+            created_at
+        "#,
+        cuid2(),
+        sid_hashed,
+        meeting_id,
+        starts,
+        ends,
+        reason as AbsenceReason,
+        note,
+        claims.sub,
+    )
+    .fetch_one(&pg)
+    .await?;
+
+    let absence_telemeter = absence.clone();
+    tokio::spawn(async move {
+        telemeter(
+            AbsenceAdd {
+                admin_id: claims.sub,
+                absence: absence_telemeter,
+            },
+            &pg,
+        )
+        .await
+        .log();
+    });
+
+    Ok(absence)
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn approve(id: String, claims: jwt::Claims, pg: PgPool) -> Result<Absence, Error> {
+    let absence = sqlx::query_as!(
+        Absence,
+        r#"
+        UPDATE absences
+        SET approved_by = $2, approved_at = NOW()
+        WHERE id = $1 AND approved_at IS NULL
+        RETURNING
+            id,
+            sid_hashed,
+            meeting_id,
+            starts,
+            ends,
+            reason AS "reason: AbsenceReason",
+            note,
+            created_by,
+            approved_by,
+            approved_at,
+            created_at
+        "#,
+        id,
+        claims.sub,
+    )
+    .fetch_optional(&pg)
+    .await?;
+
+    let Some(absence) = absence else {
+        let exists = sqlx::query!(r#"SELECT 1 AS "exists" FROM absences WHERE id = $1"#, id)
+            .fetch_optional(&pg)
+            .await?
+            .is_some();
+
+        return Err(if exists {
+            Error::conflict()
+        } else {
+            Error::not_found()
+        });
+    };
+
+    let absence_telemeter = absence.clone();
+    tokio::spawn(async move {
+        telemeter(
+            AbsenceApprove {
+                admin_id: claims.sub,
+                absence: absence_telemeter,
+            },
+            &pg,
+        )
+        .await
+        .log();
+    });
+
+    Ok(absence)
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn delete(id: String, claims: jwt::Claims, pg: PgPool) -> Result<Absence, Error> {
+    let absence = sqlx::query_as!(
+        Absence,
+        r#"
+        DELETE FROM absences
+        WHERE id = $1
+        RETURNING
+            id,
+            sid_hashed,
+            meeting_id,
+            starts,
+            ends,
+            reason AS "reason: AbsenceReason",
+            note,
+            created_by,
+            approved_by,
+            approved_at,
+            created_at
+        "#,
+        id,
+    )
+    .fetch_optional(&pg)
+    .await?
+    .ok_or(Error::not_found())?;
+
+    let absence_telemeter = absence.clone();
+    tokio::spawn(async move {
+        telemeter(
+            AbsenceDelete {
+                admin_id: claims.sub,
+                absence: absence_telemeter,
+            },
+            &pg,
+        )
+        .await
+        .log();
+    });
+
+    Ok(absence)
+}
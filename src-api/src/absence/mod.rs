@@ -0,0 +1,65 @@
+pub(crate) mod crud;
+
+pub(crate) use crud::Absence;
+
+use crate::prelude::*;
+
+pub(crate) struct AbsenceService {
+    pg: PgPool,
+}
+
+impl AbsenceService {
+    pub(crate) fn new(pg: PgPool) -> Self {
+        Self { pg }
+    }
+}
+
+#[auto_operation_ids]
+#[OpenApi(tag = "Tag::Absence", prefix_path = "/absence")]
+impl AbsenceService {
+    #[oai(path = "/", method = "get")]
+    async fn query_many(
+        &self,
+        /// Only include absences for this student
+        sid_hashed: Query<Option<String>>,
+        /// Only include absences that have not been approved
+        #[oai(default)]
+        pending: Query<bool>,
+        jwt: Jwt,
+    ) -> Result<Json<crud::QueryManyResponse>, crud::QueryManyError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursView)?;
+
+        Ok(Json(
+            crud::query_many(sid_hashed.0, pending.0, self.pg.clone()).await?,
+        ))
+    }
+
+    #[oai(path = "/", method = "post")]
+    async fn add(
+        &self,
+        request: Json<crud::CreateRequest>,
+        jwt: Jwt,
+    ) -> Result<Json<Absence>, crud::CreateError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+
+        Ok(Json(crud::add(request.0, claims, self.pg.clone()).await?))
+    }
+
+    #[oai(path = "/:id/approve", method = "post")]
+    async fn approve(&self, id: Path<String>, jwt: Jwt) -> Result<Json<Absence>, crud::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::AbsenceApprove)?;
+
+        Ok(Json(crud::approve(id.0, claims, self.pg.clone()).await?))
+    }
+
+    #[oai(path = "/:id", method = "delete")]
+    async fn delete(&self, id: Path<String>, jwt: Jwt) -> Result<Json<Absence>, crud::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+
+        Ok(Json(crud::delete(id.0, claims, self.pg.clone()).await?))
+    }
+}
@@ -0,0 +1,217 @@
+use chrono::NaiveDate;
+
+use crate::prelude::*;
+
+/// Hours credited (or deducted) outside of sign-in and sign-out, e.g. for
+/// outreach events or off-site work
+#[derive(Clone, Debug, Serialize, Deserialize, Object)]
+pub(crate) struct Adjustment {
+    pub id: String,
+    pub sid_hashed: String,
+    pub hour_type: HourType,
+    /// Negative to deduct hours
+    pub hours: f64,
+    /// The day the hours were earned
+    pub date: NaiveDate,
+    pub reason: String,
+    /// The admin who granted the hours
+    pub admin_id: Option<String>,
+    pub created_at: chrono::DateTime<Utc>,
+}
+
+#[derive(Object, Debug)]
+#[oai(rename = "AdjustmentCreateRequest")]
+pub(super) struct CreateRequest {
+    sid_hashed: String,
+    hour_type: HourType,
+    /// Negative to deduct hours, must not be zero
+    hours: f64,
+    date: NaiveDate,
+    reason: String,
+}
+
+#[derive(Object)]
+#[oai(rename = "AdjustmentQueryManyResponse")]
+pub(super) struct QueryManyResponse {
+    adjustments: Vec<Adjustment>,
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum QueryManyError {
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum Error {
+    /// `hours` is zero or not finite, or `reason` is empty
+    #[oai(status = 400)]
+    #[construct(hours, "hours must be finite and nonzero")]
+    #[construct(reason, "reason must not be empty")]
+    BadRequest(PlainText<String>),
+
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    /// No student or adjustment with the given ID exists
+    #[oai(status = 404)]
+    #[construct(student, "Student not found")]
+    #[construct(adjustment, "Adjustment not found")]
+    NotFound(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn query_many(
+    sid_hashed: Option<String>,
+    pg: PgPool,
+) -> Result<QueryManyResponse, QueryManyError> {
+    let adjustments = sqlx::query_as!(
+        Adjustment,
+        r#"
+        SELECT
+            id,
+            sid_hashed,
+            hour_type AS "hour_type: HourType",
+            hours,
+            date,
+            reason,
+            admin_id,
+            created_at
+        FROM hour_adjustments
+        WHERE ($1::text IS NULL OR sid_hashed = $1)
+        ORDER BY date DESC, created_at DESC
+        "#,
+        sid_hashed,
+    )
+    .fetch_all(&pg)
+    .await?;
+
+    Ok(QueryManyResponse { adjustments })
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn add(
+    CreateRequest {
+        sid_hashed,
+        hour_type,
+        hours,
+        date,
+        reason,
+    }: CreateRequest,
+    claims: jwt::Claims,
+    pg: PgPool,
+) -> Result<Adjustment, Error> {
+    if !hours.is_finite() || hours == 0.0 {
+        return Err(Error::hours());
+    }
+
+    if reason.trim().is_empty() {
+        return Err(Error::reason());
+    }
+
+    let adjustment = sqlx::query_as!(
+        Adjustment,
+        r#"
+        INSERT INTO hour_adjustments (id, sid_hashed, hour_type, hours, date, reason, admin_id)
+        SELECT $1, $2, $3, $4, $5, $6, $7
+        WHERE EXISTS (
+            SELECT 1
+            FROM students s
+            WHERE s.id_hashed = $2
+        )
+        RETURNING
+            id,
+            sid_hashed,
+            hour_type AS "hour_type: HourType",
+            hours,
+            date,
+            reason,
+            admin_id,
+            created_at
+        "#,
+        cuid2(),
+        sid_hashed,
+        hour_type.as_str(),
+        hours,
+        date,
+        reason,
+        claims.sub,
+    )
+    .fetch_optional(&pg)
+    .await?
+    .ok_or(Error::student())?;
+
+    let adjustment_telemeter = adjustment.clone();
+    tokio::spawn(async move {
+        telemeter(
+            AdjustmentAdd {
+                admin_id: claims.sub,
+                adjustment: adjustment_telemeter,
+            },
+            &pg,
+        )
+        .await
+        .log();
+    });
+
+    Ok(adjustment)
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn delete(
+    id: String,
+    claims: jwt::Claims,
+    pg: PgPool,
+) -> Result<Adjustment, Error> {
+    let adjustment = sqlx::query_as!(
+        Adjustment,
+        r#"
+        DELETE FROM hour_adjustments
+        WHERE id = $1
+        RETURNING
+            id,
+            sid_hashed,
+            hour_type AS "hour_type: HourType",
+            hours,
+            date,
+            reason,
+            admin_id,
+            created_at
+        "#,
+        id,
+    )
+    .fetch_optional(&pg)
+    .await?
+    .ok_or(Error::adjustment())?;
+
+    let adjustment_telemeter = adjustment.clone();
+    tokio::spawn(async move {
+        telemeter(
+            AdjustmentDelete {
+                admin_id: claims.sub,
+                adjustment: adjustment_telemeter,
+            },
+            &pg,
+        )
+        .await
+        .log();
+    });
+
+    Ok(adjustment)
+}
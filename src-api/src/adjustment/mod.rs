@@ -0,0 +1,52 @@
+pub(crate) mod crud;
+
+pub(crate) use crud::Adjustment;
+
+use crate::prelude::*;
+
+pub(crate) struct AdjustmentService {
+    pg: PgPool,
+}
+
+impl AdjustmentService {
+    pub(crate) fn new(pg: PgPool) -> Self {
+        Self { pg }
+    }
+}
+
+#[auto_operation_ids]
+#[OpenApi(tag = "Tag::Adjustment", prefix_path = "/adjustment")]
+impl AdjustmentService {
+    #[oai(path = "/", method = "get")]
+    async fn query_many(
+        &self,
+        /// Only include adjustments for this student
+        sid_hashed: Query<Option<String>>,
+        jwt: Jwt,
+    ) -> Result<Json<crud::QueryManyResponse>, crud::QueryManyError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursView)?;
+
+        Ok(Json(crud::query_many(sid_hashed.0, self.pg.clone()).await?))
+    }
+
+    #[oai(path = "/", method = "post")]
+    async fn add(
+        &self,
+        request: Json<crud::CreateRequest>,
+        jwt: Jwt,
+    ) -> Result<Json<Adjustment>, crud::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+
+        Ok(Json(crud::add(request.0, claims, self.pg.clone()).await?))
+    }
+
+    #[oai(path = "/:id", method = "delete")]
+    async fn delete(&self, id: Path<String>, jwt: Jwt) -> Result<Json<Adjustment>, crud::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+
+        Ok(Json(crud::delete(id.0, claims, self.pg.clone()).await?))
+    }
+}
@@ -95,20 +95,6 @@ pub(super) async fn start(username: String, pg: PgPool) -> Result<StartResponse,
     .execute(&pg)
     .await?;
 
-    tokio::spawn(async move {
-        if let Err(e) = sqlx::query!(
-            r#"
-            DELETE FROM login_sessions
-            WHERE created_at < NOW() - INTERVAL '10 minutes'
-            "#
-        )
-        .execute(&pg)
-        .await
-        {
-            error!("Failed to delete expired login sessions: {}", e);
-        }
-    });
-
     Ok(StartResponse {
         session: session_id,
         salt: hex::encode(srp.salt),
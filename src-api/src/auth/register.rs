@@ -86,7 +86,7 @@ pub(super) async fn start(token: String, pg: PgPool) -> Result<StartResponse, St
         sqlx::query!(
             r#"
             DELETE FROM invites
-            WHERE id = $1 OR expiry < NOW()
+            WHERE id = $1
             "#,
             token
         )
@@ -150,7 +150,7 @@ pub(super) async fn finish(
         sqlx::query!(
             r#"
             DELETE FROM register_sessions
-            WHERE id = $1 OR expiry < NOW()
+            WHERE id = $1
             "#,
             token
         )
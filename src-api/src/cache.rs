@@ -0,0 +1,133 @@
+//! In-memory copies of configuration tables, invalidated by the
+//! `invalidate:config` channel.
+//!
+//! Every configuration table notifies the channel on change, which bumps a
+//! single generation counter and so drops every cached value at once.
+//! Configuration rarely changes, so there's no point being more precise.
+//! While the listener is disconnected, notifications could be missed, so
+//! nothing is cached at all.
+
+use std::{
+    future::Future,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use sqlx::postgres::PgListener;
+use tokio::{sync::RwLock, time};
+
+use crate::prelude::*;
+
+const CHANNEL: &str = "invalidate:config";
+
+/// Bumped on every change to a configuration table
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Whether the listener is subscribed, i.e. whether cached values can be
+/// trusted
+static LISTENING: AtomicBool = AtomicBool::new(false);
+
+/// A value loaded from configuration tables, kept until any of them change
+pub(crate) struct Cached<T> {
+    value: RwLock<Option<(u64, Arc<T>)>>,
+}
+
+impl<T> Cached<T> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            value: RwLock::const_new(None),
+        }
+    }
+
+    /// The cached value, or the result of `load` if there is none or it is
+    /// stale
+    pub(crate) async fn get<F, Fut, E>(&self, load: F) -> Result<Arc<T>, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        // read before loading, so a change made while loading leaves the value
+        // stale rather than cached for good
+        let generation = GENERATION.load(Ordering::Acquire);
+        let listening = LISTENING.load(Ordering::Acquire);
+
+        if listening
+            && let Some((cached, value)) = &*self.value.read().await
+            && *cached == generation
+        {
+            return Ok(value.clone());
+        }
+
+        let value = Arc::new(load().await?);
+
+        if listening {
+            *self.value.write().await = Some((generation, value.clone()));
+        }
+
+        Ok(value)
+    }
+}
+
+fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::AcqRel);
+}
+
+/// Spawn the listener that invalidates every cached value on change
+pub(crate) fn spawn() {
+    tokio::spawn(async move {
+        loop {
+            let mut listener = match PgListener::connect(&*env::DATABASE_URL).await {
+                Ok(l) => l,
+                Err(err) => {
+                    error!(
+                        task = "cache",
+                        error = %err,
+                        "failed to connect listener, retrying in 5s"
+                    );
+                    time::sleep(Duration::from_secs(5)).await;
+                    continue;
+                }
+            };
+
+            if let Err(err) = listener.listen(CHANNEL).await {
+                error!(
+                    task = "cache",
+                    error = %err,
+                    "failed to subscribe to channel, retrying in 5s"
+                );
+                time::sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+
+            // anything could have changed while we weren't listening
+            invalidate();
+            LISTENING.store(true, Ordering::Release);
+
+            info!(task = "cache", "started config cache listener");
+
+            // unlike `recv`, stops on disconnect instead of silently
+            // reconnecting, since notifications would be lost in between
+            while let Ok(Some(notif)) = listener.try_recv().await {
+                debug!(
+                    task = "cache",
+                    table = %notif.payload(),
+                    "configuration changed, invalidating cache"
+                );
+
+                invalidate();
+            }
+
+            LISTENING.store(false, Ordering::Release);
+
+            warn!(
+                task = "cache",
+                "config cache listener disconnected, reconnecting in 5s"
+            );
+
+            time::sleep(Duration::from_secs(5)).await;
+        }
+    });
+}
@@ -72,6 +72,12 @@ pub(crate) struct Deletion<R: Row> {
     pkey: R::Key,
 }
 
+impl<R: Row> Identifiable<R::Key> for Deletion<R> {
+    fn pkey(&self) -> &R::Key {
+        &self.pkey
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Union, strum::EnumDiscriminants)]
 #[strum_discriminants(name(ReplicationType))]
 #[serde(
@@ -17,14 +17,21 @@
 #[macro_use]
 extern crate tracing;
 
+mod absence;
+mod adjustment;
 mod admin;
 mod auth;
+mod cache;
 mod dbstream;
 mod error;
+mod meeting;
 mod prelude;
 mod roster;
+mod scheduler;
+mod season;
 mod student;
 mod telemetry;
+mod trash;
 
 #[cfg(all(not(debug_assertions), feature = "serve-static"))]
 use poem::endpoint::StaticFilesEndpoint;
@@ -36,12 +43,18 @@ use sqlx::PgPool;
 pub fn oai(pg: &PgPool) -> OpenApiService<impl OpenApi, ()> {
     OpenApiService::new(
         (
+            absence::AbsenceService::new(pg.clone()),
+            adjustment::AdjustmentService::new(pg.clone()),
             admin::AdminService::new(pg.clone()),
             auth::AuthService::new(pg.clone()),
+            meeting::MeetingService::new(pg.clone()),
             roster::HourTypeService::new(pg.clone()),
             roster::RosterService::new(pg.clone()),
+            scheduler::SchedulerService::new(pg.clone()),
+            season::SeasonService::new(pg.clone()),
             student::StudentService::new(pg.clone()),
             telemetry::TelemetryService::new(pg.clone()),
+            trash::TrashService::new(pg.clone()),
         ),
         "Attendance API",
         &*env::PUBLIC_ADDRESS,
@@ -52,6 +65,11 @@ pub async fn run_server(pool: PgPool) -> Result<(), InitError> {
     let address = format!("{}:{}", *env::ADDRESS, *env::PORT);
     let service = oai(&pool);
 
+    // fail on startup rather than on the first purge
+    trash::purge::retention();
+    scheduler::spawn(pool.clone());
+    cache::spawn();
+
     let app = Route::new();
 
     #[cfg(any(debug_assertions, not(feature = "serve-static")))]
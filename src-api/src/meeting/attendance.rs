@@ -0,0 +1,294 @@
+use std::collections::{HashMap, HashSet};
+
+use super::crud::{self, Meeting};
+use crate::{prelude::*, season};
+
+/// Students signing in this long after a meeting starts are marked late
+const LATE_GRACE: chrono::Duration = chrono::Duration::minutes(5);
+
+#[derive(Object, Debug, Clone)]
+pub(super) struct StudentAttendance {
+    sid_hashed: String,
+    /// Earliest sign-in overlapping the meeting
+    first_sign_in: chrono::DateTime<Utc>,
+    /// Minutes of the meeting the student was signed in for
+    minutes: i64,
+    late: bool,
+}
+
+#[derive(Object)]
+#[oai(rename = "MeetingAttendanceResponse")]
+pub(super) struct Response {
+    meeting: Meeting,
+    attended: Vec<StudentAttendance>,
+    /// Hashed IDs of students with no record overlapping the meeting
+    absent: Vec<String>,
+    /// Hashed IDs of students who would be absent, but have an approved
+    /// absence covering the meeting
+    excused: Vec<String>,
+}
+
+#[derive(Object, Debug, Default)]
+#[oai(rename = "StudentAttendanceResponse")]
+pub(crate) struct StudentResponse {
+    /// Mandatory meetings that have ended, not including excused absences
+    total: u32,
+    attended: u32,
+    /// Included in `attended`
+    late: u32,
+    absent: u32,
+    /// Meetings missed with an approved absence, not included in `total`
+    excused: u32,
+    /// `attended / total`, or 1 if there have been no mandatory meetings
+    rate: f64,
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum Error {
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    /// Meeting with the given ID does not exist
+    #[oai(status = 404)]
+    #[construct("Meeting not found")]
+    NotFound(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+#[derive(ApiResponse, ApiError)]
+pub(crate) enum StudentError {
+    #[oai(status = 404)]
+    #[construct(season, "Season not found")]
+    NotFound(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+struct Session {
+    sign_in: chrono::DateTime<Utc>,
+    sign_out: Option<chrono::DateTime<Utc>>,
+}
+
+/// The student's attendance at a meeting spanning `window`, given all of their
+/// records of the meeting's hour type
+fn attendance<'a>(
+    (starts, ends): (chrono::DateTime<Utc>, chrono::DateTime<Utc>),
+    sessions: impl IntoIterator<Item = &'a Session>,
+) -> Option<(chrono::DateTime<Utc>, i64)> {
+    let now = Utc::now();
+    let mut first_sign_in = None;
+    let mut minutes = 0;
+
+    for session in sessions {
+        let overlap_start = session.sign_in.max(starts);
+        let overlap_end = session.sign_out.unwrap_or(now).min(ends);
+
+        if overlap_end <= overlap_start {
+            continue;
+        }
+
+        minutes += (overlap_end - overlap_start).num_minutes();
+        first_sign_in = Some(match first_sign_in {
+            Some(first) if first < session.sign_in => first,
+            _ => session.sign_in,
+        });
+    }
+
+    first_sign_in.map(|first| (first, minutes))
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn meeting(id: String, pg: PgPool) -> Result<Response, Error> {
+    let meeting = crud::fetch(&id, &pg).await?.ok_or(Error::not_found())?;
+
+    let Some((starts, ends)) = meeting.window() else {
+        return Ok(Response {
+            meeting,
+            attended: vec![],
+            absent: vec![],
+            excused: vec![],
+        });
+    };
+
+    let records = sqlx::query!(
+        r#"
+        SELECT sid_hashed, sign_in, sign_out FROM records
+        WHERE hour_type = $1
+            AND sign_in < $3
+            AND (sign_out IS NULL OR sign_out > $2)
+        "#,
+        meeting.hour_type.as_str(),
+        starts,
+        ends,
+    )
+    .fetch_all(&pg)
+    .await?;
+
+    let students = sqlx::query!(r#"SELECT id_hashed FROM students"#)
+        .fetch_all(&pg)
+        .await?;
+
+    let excused = sqlx::query_scalar!(
+        r#"
+        SELECT sid_hashed FROM absences
+        WHERE approved_at IS NOT NULL
+            AND (meeting_id = $1 OR $2 BETWEEN starts AND ends)
+        "#,
+        meeting.id,
+        meeting.date,
+    )
+    .fetch_all(&pg)
+    .await?
+    .into_iter()
+    .collect::<HashSet<_>>();
+
+    let mut sessions = HashMap::<String, Vec<Session>>::new();
+    for record in records {
+        sessions
+            .entry(record.sid_hashed)
+            .or_default()
+            .push(Session {
+                sign_in: record.sign_in,
+                sign_out: record.sign_out,
+            });
+    }
+
+    let mut attended = vec![];
+    let mut absent = vec![];
+    let mut excused_absent = vec![];
+
+    for student in students {
+        let sessions = sessions.get(&student.id_hashed).into_iter().flatten();
+
+        match attendance((starts, ends), sessions) {
+            Some((first_sign_in, minutes)) => attended.push(StudentAttendance {
+                sid_hashed: student.id_hashed,
+                first_sign_in,
+                minutes,
+                late: first_sign_in > starts + LATE_GRACE,
+            }),
+            None if excused.contains(&student.id_hashed) => excused_absent.push(student.id_hashed),
+            None => absent.push(student.id_hashed),
+        }
+    }
+
+    Ok(Response {
+        meeting,
+        attended,
+        absent,
+        excused: excused_absent,
+    })
+}
+
+/// Attendance rate of a single student over every mandatory meeting in
+/// `season` that has ended
+#[tracing::instrument(skip(pg), err)]
+pub(crate) async fn student(
+    sid_hashed: String,
+    season: Option<String>,
+    pg: PgPool,
+) -> Result<StudentResponse, StudentError> {
+    let season = season::resolve(season.as_deref(), &pg)
+        .await?
+        .ok_or(StudentError::season())?;
+
+    let meetings = sqlx::query_as!(
+        Meeting,
+        r#"
+        SELECT id, date, starts, ends, hour_type AS "hour_type: HourType", mandatory
+        FROM meetings
+        WHERE mandatory
+            AND date <= CURRENT_DATE
+            AND date >= $1
+            AND ($2::date IS NULL OR date <= $2)
+        "#,
+        season.starts,
+        season.ends,
+    )
+    .fetch_all(&pg)
+    .await?;
+
+    let records = sqlx::query!(
+        r#"
+        SELECT hour_type AS "hour_type: HourType", sign_in, sign_out FROM records
+        WHERE sid_hashed = $1
+        "#,
+        sid_hashed,
+    )
+    .fetch_all(&pg)
+    .await?;
+
+    let absences = sqlx::query!(
+        r#"
+        SELECT meeting_id, starts, ends FROM absences
+        WHERE sid_hashed = $1 AND approved_at IS NOT NULL
+        "#,
+        sid_hashed,
+    )
+    .fetch_all(&pg)
+    .await?;
+
+    let excused = |meeting: &Meeting| {
+        absences.iter().any(|absence| {
+            absence.meeting_id.as_deref() == Some(&meeting.id)
+                || absence
+                    .starts
+                    .zip(absence.ends)
+                    .is_some_and(|(starts, ends)| (starts..=ends).contains(&meeting.date))
+        })
+    };
+
+    let mut sessions = HashMap::<HourType, Vec<Session>>::new();
+    for record in records {
+        sessions.entry(record.hour_type).or_default().push(Session {
+            sign_in: record.sign_in,
+            sign_out: record.sign_out,
+        });
+    }
+
+    let now = Utc::now();
+    let mut res = StudentResponse::default();
+
+    for meeting in meetings {
+        let Some((starts, ends)) = meeting.window() else {
+            continue;
+        };
+
+        if ends > now {
+            continue;
+        }
+
+        let sessions = sessions.get(&meeting.hour_type).into_iter().flatten();
+        match attendance((starts, ends), sessions) {
+            Some((first_sign_in, _)) => {
+                res.total += 1;
+                res.attended += 1;
+                if first_sign_in > starts + LATE_GRACE {
+                    res.late += 1;
+                }
+            }
+            None if excused(&meeting) => res.excused += 1,
+            None => {
+                res.total += 1;
+                res.absent += 1;
+            }
+        }
+    }
+
+    res.rate = if res.total == 0 {
+        1.0
+    } else {
+        f64::from(res.attended) / f64::from(res.total)
+    };
+
+    Ok(res)
+}
@@ -0,0 +1,283 @@
+use chrono::{NaiveDate, NaiveTime};
+
+use crate::prelude::*;
+
+#[derive(Object, Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Meeting {
+    pub id: String,
+    pub date: NaiveDate,
+    /// In server's local time
+    pub starts: NaiveTime,
+    /// In server's local time, must be after `starts`
+    pub ends: NaiveTime,
+    pub hour_type: HourType,
+    /// Whether this meeting counts towards students' attendance rates
+    pub mandatory: bool,
+}
+
+impl Meeting {
+    /// Start and end of the meeting, or `None` if either falls in a DST gap
+    pub(crate) fn window(&self) -> Option<(chrono::DateTime<Utc>, chrono::DateTime<Utc>)> {
+        let at = |time| {
+            self.date
+                .and_time(time)
+                .and_local_timezone(Local)
+                .earliest()
+                .map(|dt| dt.with_timezone(&Utc))
+        };
+
+        Some((at(self.starts)?, at(self.ends)?))
+    }
+}
+
+#[derive(Object, Debug)]
+#[oai(rename = "MeetingCreateRequest")]
+pub(super) struct CreateRequest {
+    date: NaiveDate,
+    starts: NaiveTime,
+    ends: NaiveTime,
+    hour_type: HourType,
+    #[oai(default)]
+    mandatory: bool,
+}
+
+#[derive(Object, Debug)]
+#[oai(rename = "MeetingUpdateRequest")]
+pub(super) struct UpdateRequest {
+    #[oai(notnull)]
+    date: Option<NaiveDate>,
+    #[oai(notnull)]
+    starts: Option<NaiveTime>,
+    #[oai(notnull)]
+    ends: Option<NaiveTime>,
+    #[oai(notnull)]
+    hour_type: Option<HourType>,
+    #[oai(notnull)]
+    mandatory: Option<bool>,
+}
+
+#[derive(Object)]
+#[oai(rename = "MeetingQueryManyResponse")]
+pub(super) struct QueryManyResponse {
+    meetings: Vec<Meeting>,
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum QueryManyError {
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum Error {
+    /// `ends` is not after `starts`
+    #[oai(status = 400)]
+    #[construct(times, "ends must be after starts")]
+    BadRequest(PlainText<String>),
+
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    /// Meeting with the given ID does not exist
+    #[oai(status = 404)]
+    #[construct("Meeting not found")]
+    NotFound(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn query_many(
+    after: Option<NaiveDate>,
+    before: Option<NaiveDate>,
+    pg: PgPool,
+) -> Result<QueryManyResponse, QueryManyError> {
+    let meetings = sqlx::query_as!(
+        Meeting,
+        r#"
+        SELECT id, date, starts, ends, hour_type AS "hour_type: HourType", mandatory
+        FROM meetings
+        WHERE ($1::date IS NULL OR date >= $1)
+            AND ($2::date IS NULL OR date <= $2)
+        ORDER BY date, starts
+        "#,
+        after,
+        before,
+    )
+    .fetch_all(&pg)
+    .await?;
+
+    Ok(QueryManyResponse { meetings })
+}
+
+pub(crate) async fn fetch(id: &str, pg: &PgPool) -> Result<Option<Meeting>, sqlx::Error> {
+    sqlx::query_as!(
+        Meeting,
+        r#"
+        SELECT id, date, starts, ends, hour_type AS "hour_type: HourType", mandatory
+        FROM meetings
+        WHERE id = $1
+        "#,
+        id,
+    )
+    .fetch_optional(pg)
+    .await
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn query_one(id: String, pg: PgPool) -> Result<Meeting, Error> {
+    fetch(&id, &pg).await?.ok_or(Error::not_found())
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn add(
+    CreateRequest {
+        date,
+        starts,
+        ends,
+        hour_type,
+        mandatory,
+    }: CreateRequest,
+    claims: jwt::Claims,
+    pg: PgPool,
+) -> Result<Meeting, Error> {
+    if ends <= starts {
+        return Err(Error::times());
+    }
+
+    let meeting = sqlx::query_as!(
+        Meeting,
+        r#"
+        INSERT INTO meetings (id, date, starts, ends, hour_type, mandatory)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, date, starts, ends, hour_type AS "hour_type: HourType", mandatory
+        "#,
+        cuid2(),
+        date,
+        starts,
+        ends,
+        hour_type.as_str(),
+        mandatory,
+    )
+    .fetch_one(&pg)
+    .await?;
+
+    let meeting_telemeter = meeting.clone();
+    tokio::spawn(async move {
+        telemeter(
+            MeetingAdd {
+                admin_id: claims.sub,
+                meeting: meeting_telemeter,
+            },
+            &pg,
+        )
+        .await
+        .log();
+    });
+
+    Ok(meeting)
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn update(
+    id: String,
+    UpdateRequest {
+        date,
+        starts,
+        ends,
+        hour_type,
+        mandatory,
+    }: UpdateRequest,
+    claims: jwt::Claims,
+    pg: PgPool,
+) -> Result<Meeting, Error> {
+    let old = fetch(&id, &pg).await?.ok_or(Error::not_found())?;
+
+    if ends.unwrap_or(old.ends) <= starts.unwrap_or(old.starts) {
+        return Err(Error::times());
+    }
+
+    let meeting = sqlx::query_as!(
+        Meeting,
+        r#"
+        UPDATE meetings
+        SET
+            date = COALESCE($2, date),
+            starts = COALESCE($3, starts),
+            ends = COALESCE($4, ends),
+            hour_type = COALESCE($5, hour_type),
+            mandatory = COALESCE($6, mandatory)
+        WHERE id = $1
+        RETURNING id, date, starts, ends, hour_type AS "hour_type: HourType", mandatory
+        "#,
+        id,
+        date,
+        starts,
+        ends,
+        hour_type.as_ref().map(HourType::as_str),
+        mandatory,
+    )
+    .fetch_one(&pg) // checked for existence above
+    .await?;
+
+    let meeting_telemeter = meeting.clone();
+    tokio::spawn(async move {
+        telemeter(
+            MeetingEdit {
+                admin_id: claims.sub,
+                old,
+                meeting: meeting_telemeter,
+            },
+            &pg,
+        )
+        .await
+        .log();
+    });
+
+    Ok(meeting)
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn delete(id: String, claims: jwt::Claims, pg: PgPool) -> Result<Meeting, Error> {
+    let meeting = sqlx::query_as!(
+        Meeting,
+        r#"
+        DELETE FROM meetings
+        WHERE id = $1
+        RETURNING id, date, starts, ends, hour_type AS "hour_type: HourType", mandatory
+        "#,
+        id,
+    )
+    .fetch_optional(&pg)
+    .await?
+    .ok_or(Error::not_found())?;
+
+    let meeting_telemeter = meeting.clone();
+    tokio::spawn(async move {
+        telemeter(
+            MeetingDelete {
+                admin_id: claims.sub,
+                meeting: meeting_telemeter,
+            },
+            &pg,
+        )
+        .await
+        .log();
+    });
+
+    Ok(meeting)
+}
@@ -0,0 +1,96 @@
+pub(crate) mod attendance;
+pub(crate) mod crud;
+
+use chrono::NaiveDate;
+
+use crate::prelude::*;
+
+pub(crate) struct MeetingService {
+    pg: PgPool,
+}
+
+impl MeetingService {
+    pub(crate) fn new(pg: PgPool) -> Self {
+        Self { pg }
+    }
+}
+
+#[auto_operation_ids]
+#[OpenApi(tag = "Tag::Meeting", prefix_path = "/meeting")]
+impl MeetingService {
+    #[oai(path = "/", method = "get")]
+    async fn query_many(
+        &self,
+        /// Only include meetings on or after this date
+        after: Query<Option<NaiveDate>>,
+        /// Only include meetings on or before this date
+        before: Query<Option<NaiveDate>>,
+        jwt: Jwt,
+    ) -> Result<Json<crud::QueryManyResponse>, crud::QueryManyError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursView)?;
+
+        Ok(Json(
+            crud::query_many(after.0, before.0, self.pg.clone()).await?,
+        ))
+    }
+
+    #[oai(path = "/:id", method = "get")]
+    async fn query_one(
+        &self,
+        id: Path<String>,
+        jwt: Jwt,
+    ) -> Result<Json<crud::Meeting>, crud::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursView)?;
+
+        Ok(Json(crud::query_one(id.0, self.pg.clone()).await?))
+    }
+
+    #[oai(path = "/", method = "post")]
+    async fn add(
+        &self,
+        request: Json<crud::CreateRequest>,
+        jwt: Jwt,
+    ) -> Result<Json<crud::Meeting>, crud::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+
+        Ok(Json(crud::add(request.0, claims, self.pg.clone()).await?))
+    }
+
+    #[oai(path = "/:id", method = "patch")]
+    async fn update(
+        &self,
+        id: Path<String>,
+        request: Json<crud::UpdateRequest>,
+        jwt: Jwt,
+    ) -> Result<Json<crud::Meeting>, crud::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+
+        Ok(Json(
+            crud::update(id.0, request.0, claims, self.pg.clone()).await?,
+        ))
+    }
+
+    #[oai(path = "/:id", method = "delete")]
+    async fn delete(&self, id: Path<String>, jwt: Jwt) -> Result<Json<crud::Meeting>, crud::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+
+        Ok(Json(crud::delete(id.0, claims, self.pg.clone()).await?))
+    }
+
+    #[oai(path = "/:id/attendance", method = "get")]
+    async fn attendance(
+        &self,
+        id: Path<String>,
+        jwt: Jwt,
+    ) -> Result<Json<attendance::Response>, attendance::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursView)?;
+
+        Ok(Json(attendance::meeting(id.0, self.pg.clone()).await?))
+    }
+}
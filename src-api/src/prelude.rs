@@ -21,6 +21,7 @@ pub mod env {
     static_env!(pub(crate) PORT or 8080);
     static_env!(pub(crate) ADDRESS or "0.0.0.0");
     static_env!(pub(crate) PUBLIC_ADDRESS or _PUBLIC_ADDRESS);
+    static_env!(pub(crate) TRASH_RETENTION_DAYS or 30);
 }
 
 pub(crate) trait DateTimeExt {
@@ -36,12 +37,18 @@ impl DateTimeExt for chrono::DateTime<Utc> {
 
 #[derive(poem_openapi::Tags)]
 pub(crate) enum Tag {
+    Absence,
+    Adjustment,
     Admin,
     Auth,
     HourType,
+    Meeting,
     Roster,
+    Scheduler,
+    Season,
     Student,
     Telemetry,
+    Trash,
 }
 
 pub(crate) use attendance_api_macro::auto_operation_ids;
@@ -0,0 +1,277 @@
+use chrono::{Days, NaiveTime};
+
+use crate::{dbstream::Record, prelude::*, roster::session::SessionPolicy};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Enum, sqlx::Type)]
+#[oai(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "auto_close_policy", rename_all = "snake_case")]
+pub(crate) enum AutoClosePolicy {
+    /// Sign out at `cutoff` on the day of sign-in
+    Cutoff,
+    /// Sign out `credit` hours after sign-in, once the day has ended. Capped at
+    /// the end of the day of sign-in.
+    Credit,
+    /// Close the record with no credit and flag it for review, once the day
+    /// has ended
+    Flag,
+}
+
+#[derive(Object, Debug, Clone, Copy)]
+pub(super) struct AutoCloseConfig {
+    pub policy: AutoClosePolicy,
+    /// Local time of day. Only used by the `cutoff` policy.
+    ///
+    /// Records signed in after the cutoff are closed with no credit and
+    /// flagged at midnight instead.
+    pub cutoff: NaiveTime,
+    /// In hours, can be fractional, must be nonnegative. Only used by the
+    /// `credit` policy.
+    ///
+    /// If zero, records are closed with no credit and flagged at midnight
+    /// instead.
+    pub credit: f64,
+}
+
+/// When to sign a stale open record out, and whether to flag it for review
+#[derive(Debug, Clone, Copy)]
+struct Close {
+    at: chrono::DateTime<Utc>,
+    flagged: bool,
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum AutoCloseError {
+    #[oai(status = 400)]
+    #[construct(credit, "credit must be nonnegative")]
+    BadRequest(PlainText<String>),
+
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    #[oai(status = 404)]
+    #[construct("No auto-close configuration for this hour type")]
+    NotFound(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+impl AutoCloseConfig {
+    /// When to close an open record signed in at `sign_in`, if it is stale.
+    ///
+    /// Multi-day sessions are left alone until they outlive `span`, at which
+    /// point they are closed at the end of the span and flagged. Records with
+    /// nothing sensible to credit are closed at sign-in and flagged.
+    fn close(
+        &self,
+        sign_in: chrono::DateTime<Utc>,
+        span: Option<chrono::Duration>,
+    ) -> Option<Close> {
+        let now = Utc::now();
+
+        if let Some(span) = span {
+            return (now >= sign_in + span).then_some(Close {
+                at: sign_in + span,
+                flagged: true,
+            });
+        }
+
+        let local_in = sign_in.and_local();
+        let midnight = (local_in.date_naive() + Days::new(1))
+            .and_time(NaiveTime::MIN)
+            .and_local_timezone(Local)
+            .earliest()?
+            .with_timezone(&Utc);
+
+        let unresolved = Close {
+            at: sign_in,
+            flagged: true,
+        };
+
+        match self.policy {
+            AutoClosePolicy::Cutoff => {
+                let cutoff = local_in
+                    .date_naive()
+                    .and_time(self.cutoff)
+                    .and_local_timezone(Local)
+                    .earliest()?
+                    .with_timezone(&Utc);
+
+                if cutoff <= sign_in {
+                    // signed in after the cutoff, nothing sensible to credit
+                    return (now >= midnight).then_some(unresolved);
+                }
+
+                (now >= cutoff).then_some(Close {
+                    at: cutoff,
+                    flagged: false,
+                })
+            }
+            AutoClosePolicy::Credit => {
+                let credit = chrono::Duration::seconds((self.credit * 3600.0) as i64);
+
+                if credit <= chrono::Duration::zero() {
+                    return (now >= midnight).then_some(unresolved);
+                }
+
+                // stay on the day of sign-in, whose last instant at database
+                // precision is just before midnight, and never end in the future
+                let end_of_day = midnight - chrono::Duration::microseconds(1);
+
+                (now >= midnight).then_some(Close {
+                    at: (sign_in + credit).min(end_of_day).min(now),
+                    flagged: false,
+                })
+            }
+            AutoClosePolicy::Flag => (now >= midnight).then_some(unresolved),
+        }
+    }
+}
+
+impl HourType {
+    pub(super) async fn auto_close(&self, pg: PgPool) -> Result<AutoCloseConfig, AutoCloseError> {
+        let res = sqlx::query_as!(
+            AutoCloseConfig,
+            r#"
+            SELECT policy AS "policy: AutoClosePolicy", cutoff, credit
+            FROM auto_close_config
+            WHERE kind = $1
+            "#,
+            self.as_str(),
+        )
+        .fetch_optional(&pg)
+        .await?
+        .ok_or(AutoCloseError::not_found())?;
+
+        Ok(res)
+    }
+
+    pub(super) async fn update_auto_close(
+        &self,
+        AutoCloseConfig {
+            policy,
+            cutoff,
+            credit,
+        }: AutoCloseConfig,
+        pg: PgPool,
+    ) -> Result<(), AutoCloseError> {
+        if credit < 0.0 {
+            return Err(AutoCloseError::credit());
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO auto_close_config (kind, policy, cutoff, credit)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (kind) DO UPDATE
+            SET policy = EXCLUDED.policy,
+                cutoff = EXCLUDED.cutoff,
+                credit = EXCLUDED.credit
+            "#,
+            self.as_str(),
+            policy as AutoClosePolicy,
+            cutoff,
+            credit,
+        )
+        .execute(&pg)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Close a single stale record. Returns None if it was closed since it was
+/// fetched, e.g. because the student swiped out.
+async fn apply(id: &str, close: Close, pg: &PgPool) -> Result<Option<Record>, sqlx::Error> {
+    sqlx::query_as::<_, Record>(
+        r#"
+        UPDATE records
+        SET sign_out = $2, flagged = flagged OR $3
+        WHERE id = $1 AND sign_out IS NULL
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .bind(close.at)
+    .bind(close.flagged)
+    .fetch_optional(pg)
+    .await
+}
+
+/// Close every stale open record according to its hour type's policy.
+///
+/// A record that fails to close is logged and skipped, so it can't hold up the
+/// rest.
+#[tracing::instrument(skip(pg), err)]
+pub(crate) async fn sweep(pg: &PgPool) -> Result<(), sqlx::Error> {
+    let open = sqlx::query!(
+        r#"
+        SELECT
+            r.id,
+            r.hour_type AS "hour_type: HourType",
+            r.sign_in,
+            r.multi_day,
+            c.policy AS "policy: AutoClosePolicy",
+            c.cutoff,
+            c.credit
+        FROM records r
+        JOIN auto_close_config c ON c.kind = r.hour_type
+        WHERE r.sign_out IS NULL
+        "#
+    )
+    .fetch_all(pg)
+    .await?;
+
+    let policies = SessionPolicy::all(pg).await?;
+
+    for rec in open {
+        let config = AutoCloseConfig {
+            policy: rec.policy,
+            cutoff: rec.cutoff,
+            credit: rec.credit,
+        };
+
+        let session = policies.get(&rec.hour_type).copied().unwrap_or_default();
+        let span = session
+            .spans_days(rec.multi_day)
+            .then(|| session.max_span());
+
+        let Some(close) = config.close(rec.sign_in, span) else {
+            continue;
+        };
+
+        let record = match apply(&rec.id, close, pg).await {
+            Ok(Some(record)) => record,
+            Ok(None) => continue,
+            Err(e) => {
+                error!(record_id = %rec.id, "failed to auto-close record: {e}");
+                continue;
+            }
+        };
+
+        info!(
+            record_id = %record.id,
+            policy = ?config.policy,
+            ?close,
+            "auto-closed stale record"
+        );
+
+        telemeter(
+            RecordAutoClose {
+                policy: config.policy,
+                record,
+            },
+            pg,
+        )
+        .await
+        .log();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use crate::prelude::*;
+
+/// An RFID/NFC badge, usually a school ID, that can be swiped in place of a
+/// student ID
+#[derive(Object, Debug, Clone)]
+pub(super) struct Badge {
+    id: String,
+    uid_hashed: String,
+    sid_hashed: String,
+    enrolled_by: Option<String>,
+    enrolled_at: chrono::DateTime<Utc>,
+    /// Null until revoked. Revoked badges are unknown to swipes.
+    revoked_at: Option<chrono::DateTime<Utc>>,
+}
+
+#[derive(Object, Debug)]
+#[oai(rename = "BadgeEnrollRequest")]
+pub(super) struct EnrollRequest {
+    /// Hashed UID read from the badge
+    uid_hashed: String,
+    sid_hashed: String,
+}
+
+#[derive(Object)]
+#[oai(rename = "BadgeQueryManyResponse")]
+pub(super) struct QueryManyResponse {
+    badges: Vec<Badge>,
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum BadgeError {
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    /// No student or unrevoked badge with the given ID exists
+    #[oai(status = 404)]
+    #[construct(student, "Student not found")]
+    #[construct(badge, "Badge not found")]
+    NotFound(PlainText<String>),
+
+    /// The badge is already enrolled, and must be revoked first
+    #[oai(status = 409)]
+    #[construct("Badge already enrolled")]
+    Conflict(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+/// Map unique violations, i.e. a concurrent enrollment of the same badge, to a
+/// conflict
+fn conflict(err: sqlx::Error) -> BadgeError {
+    match &err {
+        sqlx::Error::Database(db) if db.code().as_deref() == Some("23505") => {
+            BadgeError::conflict()
+        }
+        _ => err.into(),
+    }
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn query_many(
+    sid_hashed: Option<String>,
+    pg: PgPool,
+) -> Result<QueryManyResponse, BadgeError> {
+    let badges = sqlx::query_as!(
+        Badge,
+        r#"
+        SELECT id, uid_hashed, sid_hashed, enrolled_by, enrolled_at, revoked_at
+        FROM badges
+        WHERE ($1::text IS NULL OR sid_hashed = $1)
+        ORDER BY enrolled_at DESC
+        "#,
+        sid_hashed,
+    )
+    .fetch_all(&pg)
+    .await?;
+
+    Ok(QueryManyResponse { badges })
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn enroll(
+    EnrollRequest {
+        uid_hashed,
+        sid_hashed,
+    }: EnrollRequest,
+    admin_id: String,
+    pg: PgPool,
+) -> Result<Badge, BadgeError> {
+    if student(&uid_hashed, &pg).await?.is_some() {
+        return Err(BadgeError::conflict());
+    }
+
+    sqlx::query_as!(
+        Badge,
+        r#"
+        INSERT INTO badges (id, uid_hashed, sid_hashed, enrolled_by)
+        SELECT $1, $2, $3, $4
+        WHERE EXISTS (
+            SELECT 1
+            FROM students s
+            WHERE s.id_hashed = $3
+        )
+        RETURNING id, uid_hashed, sid_hashed, enrolled_by, enrolled_at, revoked_at
+        "#,
+        cuid2(),
+        uid_hashed,
+        sid_hashed,
+        admin_id,
+    )
+    .fetch_optional(&pg)
+    .await
+    .map_err(conflict)?
+    .ok_or(BadgeError::student())
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn revoke(id: String, pg: PgPool) -> Result<Badge, BadgeError> {
+    sqlx::query_as!(
+        Badge,
+        r#"
+        UPDATE badges
+        SET revoked_at = NOW()
+        WHERE id = $1 AND revoked_at IS NULL
+        RETURNING id, uid_hashed, sid_hashed, enrolled_by, enrolled_at, revoked_at
+        "#,
+        id,
+    )
+    .fetch_optional(&pg)
+    .await?
+    .ok_or(BadgeError::badge())
+}
+
+/// The student an unrevoked badge is enrolled to, if any
+pub(super) async fn student(uid_hashed: &str, pg: &PgPool) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT sid_hashed FROM badges
+        WHERE uid_hashed = $1 AND revoked_at IS NULL
+        "#,
+        uid_hashed,
+    )
+    .fetch_optional(pg)
+    .await
+}
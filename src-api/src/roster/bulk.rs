@@ -0,0 +1,328 @@
+use poem_openapi::Union;
+
+use super::{
+    overlap,
+    session::{OverlapPolicy, SessionPolicy},
+};
+use crate::{dbstream::Record, prelude::*, trash};
+
+/// Set the given fields of every record, leaving the rest untouched
+#[derive(Object, Serialize, Deserialize, Clone, Debug)]
+pub(crate) struct BulkEdit {
+    /// Move the records to another student
+    sid_hashed: Option<String>,
+    flagged: Option<bool>,
+    multi_day: Option<bool>,
+}
+
+#[derive(Object, Serialize, Deserialize, Clone, Debug)]
+pub(crate) struct BulkDelete {}
+
+#[derive(Object, Serialize, Deserialize, Clone, Debug)]
+pub(crate) struct BulkRetype {
+    hour_type: HourType,
+}
+
+/// Move both the sign-in and sign-out of every record
+#[derive(Object, Serialize, Deserialize, Clone, Debug)]
+pub(crate) struct BulkShift {
+    /// Negative to move records earlier
+    seconds: i64,
+}
+
+#[derive(Union, Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "operation", rename_all = "snake_case")]
+#[oai(
+    rename = "BulkOperation",
+    discriminator_name = "operation",
+    rename_all = "snake_case"
+)]
+pub(crate) enum BulkOperation {
+    Edit(BulkEdit),
+    Delete(BulkDelete),
+    Retype(BulkRetype),
+    Shift(BulkShift),
+}
+
+#[derive(Object)]
+#[oai(rename = "RosterBulkRequest")]
+pub(super) struct Request {
+    record_ids: Vec<String>,
+    operation: BulkOperation,
+    /// Validate and apply the operation, but roll it back instead of
+    /// committing
+    #[oai(default)]
+    dry_run: bool,
+}
+
+#[derive(Object)]
+#[oai(rename = "RosterBulkResponse")]
+pub(super) struct Response {
+    /// Every affected record after the operation, earliest sign-in first. For
+    /// deletions, the records that were moved to the trash.
+    records: Vec<Record>,
+    /// Whether the operation was rolled back
+    dry_run: bool,
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum Error {
+    /// No records were given, or a resulting record would be invalid as a
+    /// manual edit
+    #[oai(status = 400)]
+    #[construct(empty, "No records given")]
+    #[construct(
+        time_out(String),
+        "Record {source}: time_out must be after and on the same day as time_in"
+    )]
+    #[construct(
+        too_short(String),
+        "Record {source}: session is shorter than the minimum for this hour type"
+    )]
+    #[construct(
+        too_long(String),
+        "Record {source}: session is longer than the maximum span for this hour type"
+    )]
+    BadRequest(PlainText<String>),
+
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    /// At least one of the given records does not exist
+    #[oai(status = 404)]
+    #[construct("Record not found")]
+    NotFound(PlainText<String>),
+
+    /// A resulting record would overlap another record of the same student and
+    /// hour type, and the hour type rejects overlaps
+    #[oai(status = 409)]
+    #[construct(
+        overlap(String),
+        "Record {source} would overlap another record of this student and hour type"
+    )]
+    Conflict(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+impl BulkOperation {
+    /// Whether the operation can make a session invalid
+    fn constrains_session(&self) -> bool {
+        match self {
+            Self::Edit(edit) => edit.multi_day.is_some(),
+            Self::Delete(_) => false,
+            Self::Retype(_) | Self::Shift(_) => true,
+        }
+    }
+
+    /// Whether the operation can make a session overlap another
+    fn may_overlap(&self) -> bool {
+        match self {
+            Self::Edit(edit) => edit.sid_hashed.is_some(),
+            Self::Delete(_) => false,
+            Self::Retype(_) | Self::Shift(_) => true,
+        }
+    }
+}
+
+fn validate(record: &Record, policy: &SessionPolicy) -> Result<(), Error> {
+    let Some(sign_out) = record.sign_out else {
+        return Ok(());
+    };
+
+    let duration = sign_out - record.sign_in;
+
+    if duration < chrono::Duration::zero() {
+        return Err(Error::time_out(record.id.clone()));
+    }
+
+    if policy.spans_days(record.multi_day) {
+        if duration > policy.max_span() {
+            return Err(Error::too_long(record.id.clone()));
+        }
+    } else if sign_out.and_local().date_naive() != record.sign_in.and_local().date_naive() {
+        return Err(Error::time_out(record.id.clone()));
+    }
+
+    if policy.too_short(duration) {
+        return Err(Error::too_short(record.id.clone()));
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn route(
+    Request {
+        mut record_ids,
+        operation,
+        dry_run,
+    }: Request,
+    claims: jwt::Claims,
+    pg: PgPool,
+) -> Result<Response, Error> {
+    record_ids.sort();
+    record_ids.dedup();
+
+    if record_ids.is_empty() {
+        return Err(Error::empty());
+    }
+
+    let mut tx = pg.begin().await?;
+
+    let old = sqlx::query_as::<_, Record>(
+        r#"
+        SELECT *
+        FROM records
+        WHERE id = ANY($1)
+        ORDER BY sign_in
+        FOR UPDATE
+        "#,
+    )
+    .bind(&record_ids)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if old.len() != record_ids.len() {
+        return Err(Error::not_found());
+    }
+
+    let mut records = match &operation {
+        BulkOperation::Edit(BulkEdit {
+            sid_hashed,
+            flagged,
+            multi_day,
+        }) => {
+            sqlx::query_as::<_, Record>(
+                r#"
+                UPDATE records
+                SET
+                    sid_hashed = COALESCE($2, sid_hashed),
+                    flagged = COALESCE($3, flagged),
+                    multi_day = COALESCE($4, multi_day)
+                WHERE id = ANY($1)
+                RETURNING *
+                "#,
+            )
+            .bind(&record_ids)
+            .bind(sid_hashed)
+            .bind(flagged)
+            .bind(multi_day)
+            .fetch_all(&mut *tx)
+            .await?
+        }
+        BulkOperation::Delete(_) => {
+            trash::trash_records(&record_ids, &claims.sub, &mut *tx).await?
+        }
+        BulkOperation::Retype(BulkRetype { hour_type }) => {
+            sqlx::query_as::<_, Record>(
+                r#"
+                UPDATE records
+                SET hour_type = $2
+                WHERE id = ANY($1)
+                RETURNING *
+                "#,
+            )
+            .bind(&record_ids)
+            .bind(hour_type)
+            .fetch_all(&mut *tx)
+            .await?
+        }
+        BulkOperation::Shift(BulkShift { seconds }) => {
+            sqlx::query_as::<_, Record>(
+                r#"
+                UPDATE records
+                SET
+                    sign_in = sign_in + make_interval(secs => $2),
+                    sign_out = sign_out + make_interval(secs => $2)
+                WHERE id = ANY($1)
+                RETURNING *
+                "#,
+            )
+            .bind(&record_ids)
+            .bind(*seconds as f64)
+            .fetch_all(&mut *tx)
+            .await?
+        }
+    };
+    records.sort_by_key(|record| record.sign_in);
+
+    if operation.constrains_session() || operation.may_overlap() {
+        let policies = SessionPolicy::all(&pg).await?;
+        let mut flag = Vec::new();
+
+        for record in &records {
+            let policy = policies.get(&record.hour_type).copied().unwrap_or_default();
+
+            if operation.constrains_session() {
+                validate(record, &policy)?;
+            }
+
+            // records are checked against each other as they are after the
+            // operation, since it has been applied within this transaction
+            if operation.may_overlap()
+                && policy.overlap != OverlapPolicy::Allow
+                && overlap::overlaps(
+                    &record.sid_hashed,
+                    &record.hour_type,
+                    record.sign_in,
+                    record.sign_out,
+                    Some(&record.id),
+                    &mut *tx,
+                )
+                .await?
+            {
+                if policy.overlap == OverlapPolicy::Reject {
+                    return Err(Error::overlap(record.id.clone()));
+                }
+
+                flag.push(record.id.clone());
+            }
+        }
+
+        if !flag.is_empty() {
+            sqlx::query!(
+                r#"UPDATE records SET flagged = true WHERE id = ANY($1)"#,
+                &flag,
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            for record in &mut records {
+                if flag.contains(&record.id) {
+                    record.flagged = true;
+                }
+            }
+        }
+    }
+
+    if dry_run {
+        tx.rollback().await?;
+
+        return Ok(Response { records, dry_run });
+    }
+
+    tx.commit().await?;
+
+    tokio::spawn(async move {
+        telemeter(
+            RecordBulk {
+                admin_id: claims.sub,
+                record_ids,
+                operation,
+                old,
+            },
+            &pg,
+        )
+        .await
+        .log();
+    });
+
+    Ok(Response { records, dry_run })
+}
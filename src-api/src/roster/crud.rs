@@ -2,9 +2,11 @@ use std::collections::HashMap;
 
 use poem_openapi::types::MaybeUndefined;
 
+use super::{overlap, session::OverlapPolicy};
 use crate::{
     dbstream::{PartialRecord, Record, Row},
     prelude::*,
+    trash,
 };
 
 #[derive(Object)]
@@ -13,8 +15,12 @@ pub(super) struct CreateRequest {
     sid_hashed: String,
     kind: HourType,
     time_in: chrono::DateTime<Utc>,
-    /// Must be after and on the same day as `time_in` (in server's local time)
+    /// Must be after and on the same day as `time_in` (in server's local time),
+    /// unless the session spans multiple days
     time_out: Option<chrono::DateTime<Utc>>,
+    /// Allow this session to span midnight, even if its hour type does not
+    #[oai(default)]
+    multi_day: bool,
 }
 
 pub(super) type UpdateRequest = PartialRecord;
@@ -78,9 +84,13 @@ pub(super) enum GetOneError {
 #[derive(ApiResponse, ApiError)]
 #[from(JwtVerifyError, PermissionDeniedError)]
 pub(super) enum CreateError {
-    /// `time_out` is before or on a different day than `time_in`
+    /// `time_out` is before or on a different day than `time_in`, or the
+    /// session is shorter than the hour type's minimum session length or longer
+    /// than its maximum multi-day span
     #[oai(status = 400)]
     #[construct(time_out, "time_out must be after and on the same day as time_in")]
+    #[construct(too_short, "Session is shorter than the minimum for this hour type")]
+    #[construct(too_long, "Session is longer than the maximum span for this hour type")]
     BadRequest(PlainText<String>),
 
     #[oai(status = 401)]
@@ -89,6 +99,15 @@ pub(super) enum CreateError {
     #[oai(status = 403)]
     Forbidden(PlainText<String>),
 
+    /// The session overlaps another record of the same student and hour type,
+    /// and the hour type rejects overlaps
+    #[oai(status = 409)]
+    #[construct(
+        overlap,
+        "Session overlaps another record of this student and hour type"
+    )]
+    Conflict(PlainText<String>),
+
     #[oai(status = 500)]
     #[from(sqlx::Error, "Database error")]
     InternalServerError(PlainText<String>),
@@ -97,9 +116,13 @@ pub(super) enum CreateError {
 #[derive(ApiResponse, ApiError)]
 #[from(JwtVerifyError, PermissionDeniedError)]
 pub(super) enum UpdateError {
-    /// `time_out` is before or on a different day than `time_in`
+    /// `time_out` is before or on a different day than `time_in`, or the
+    /// session is shorter than the hour type's minimum session length or longer
+    /// than its maximum multi-day span
     #[oai(status = 400)]
     #[construct(time_out, "time_out must be after and on the same day as time_in")]
+    #[construct(too_short, "Session is shorter than the minimum for this hour type")]
+    #[construct(too_long, "Session is longer than the maximum span for this hour type")]
     BadRequest(PlainText<String>),
 
     #[oai(status = 401)]
@@ -113,6 +136,15 @@ pub(super) enum UpdateError {
     #[construct("Record not found")]
     NotFound(PlainText<String>),
 
+    /// The session overlaps another record of the same student and hour type,
+    /// and the hour type rejects overlaps
+    #[oai(status = 409)]
+    #[construct(
+        overlap,
+        "Session overlaps another record of this student and hour type"
+    )]
+    Conflict(PlainText<String>),
+
     #[oai(status = 500)]
     #[from(sqlx::Error, "Database error")]
     InternalServerError(PlainText<String>),
@@ -167,22 +199,49 @@ pub(super) async fn add(
         kind,
         time_in,
         time_out,
+        multi_day,
     }: CreateRequest,
     claims: jwt::Claims,
     pg: PgPool,
 ) -> Result<CreateResponse, CreateError> {
+    let policy = kind.session_policy(&pg).await?;
+
     if let Some(to) = time_out {
-        let local_in = time_in.with_timezone(&Local);
-        let local_out = to.with_timezone(&Local);
-        if local_out.date_naive() != local_in.date_naive() || local_out < local_in {
+        if to < time_in {
+            return Err(CreateError::time_out());
+        }
+
+        if policy.spans_days(multi_day) {
+            if to - time_in > policy.max_span() {
+                return Err(CreateError::too_long());
+            }
+        } else if to.and_local().date_naive() != time_in.and_local().date_naive() {
             return Err(CreateError::time_out());
         }
+
+        if policy.too_short(to - time_in) {
+            return Err(CreateError::too_short());
+        }
     }
 
+    let flagged = match policy.overlap {
+        OverlapPolicy::Allow => false,
+        overlap => {
+            let overlaps =
+                overlap::overlaps(&sid_hashed, &kind, time_in, time_out, None, &pg).await?;
+
+            if overlaps && overlap == OverlapPolicy::Reject {
+                return Err(CreateError::overlap());
+            }
+
+            overlaps
+        }
+    };
+
     let res = sqlx::query_as::<_, Record>(
         r#"
-        INSERT INTO records (id, sid_hashed, hour_type, sign_in, sign_out)
-        VALUES ($1, $2, $3, $4, $5)
+        INSERT INTO records (id, sid_hashed, hour_type, sign_in, sign_out, multi_day, flagged)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
         RETURNING *
         "#,
     )
@@ -191,6 +250,8 @@ pub(super) async fn add(
     .bind(kind)
     .bind(time_in)
     .bind(time_out)
+    .bind(multi_day)
+    .bind(flagged)
     .fetch_one(&pg)
     .await?;
 
@@ -217,79 +278,88 @@ pub(super) async fn update(
     claims: jwt::Claims,
     pg: PgPool,
 ) -> Result<UpdateResponse, UpdateError> {
+    let old = sqlx::query_as::<_, Record>(
+        r#"
+        SELECT *
+        FROM records
+        WHERE id = $1
+        "#,
+    )
+    .bind(&incoming.id)
+    .fetch_optional(&pg)
+    .await?
+    .ok_or(UpdateError::not_found())?;
+
     'ok: {
         // if we set sign_out to null, skip all checks
         if let MaybeUndefined::Null = incoming.sign_out {
             break 'ok;
         }
 
-        // if we don't touch them, no need to validate
+        // if we don't touch anything that constrains the session, no need to
+        // validate
         if incoming.sign_in.is_none()
+            && incoming.hour_type.is_none()
+            && incoming.multi_day.is_none()
             && let MaybeUndefined::Undefined = incoming.sign_out
         {
             break 'ok;
         }
 
         // here, sign_out is either Undefined or present
-        let sign_out = match incoming.sign_out.value() {
-            Some(out) => *out,
-            None => {
-                let Some(out) = sqlx::query!(
-                    r#"
-                    SELECT sign_out FROM records
-                    WHERE id = $1
-                    "#,
-                    incoming.id,
-                )
-                .fetch_optional(&pg)
-                .await?
-                .ok_or(UpdateError::not_found())?
-                .sign_out
-                else {
-                    // if sign_out is null in db and we are not updating it, skip checks
-                    break 'ok;
-                };
-
-                out
-            }
+        let Some(sign_out) = incoming.sign_out.value().copied().or(old.sign_out) else {
+            // if sign_out is null in db and we are not updating it, skip checks
+            break 'ok;
         };
 
-        let sign_in = match incoming.sign_in {
-            Some(in_time) => in_time,
-            None => {
-                sqlx::query!(
-                    r#"
-                    SELECT sign_in FROM records
-                    WHERE id = $1
-                    "#,
-                    incoming.id,
-                )
-                .fetch_optional(&pg)
-                .await?
-                .ok_or(UpdateError::not_found())?
-                .sign_in
-            }
-        };
+        let sign_in = incoming.sign_in.unwrap_or(old.sign_in);
+        let kind = incoming.hour_type.as_ref().unwrap_or(&old.hour_type);
+        let multi_day = incoming.multi_day.unwrap_or(old.multi_day);
+        let policy = kind.session_policy(&pg).await?;
 
-        let local_in = sign_in.with_timezone(&Local);
-        let local_out = sign_out.with_timezone(&Local);
+        if sign_out < sign_in {
+            return Err(UpdateError::time_out());
+        }
 
-        if local_out.date_naive() != local_in.date_naive() || local_out < local_in {
+        if policy.spans_days(multi_day) {
+            if sign_out - sign_in > policy.max_span() {
+                return Err(UpdateError::too_long());
+            }
+        } else if sign_out.and_local().date_naive() != sign_in.and_local().date_naive() {
             return Err(UpdateError::time_out());
         }
+
+        if policy.too_short(sign_out - sign_in) {
+            return Err(UpdateError::too_short());
+        }
     };
 
-    let old = sqlx::query_as::<_, Record>(
-        r#"
-        SELECT *
-        FROM records
-        WHERE id = $1
-        "#,
-    )
-    .bind(&incoming.id)
-    .fetch_optional(&pg)
-    .await?
-    .ok_or(UpdateError::not_found())?;
+    // flag the record if it overlaps under a flagging policy, unless the
+    // request sets the flag itself
+    let mut flagged = incoming.flagged;
+
+    if incoming.sid_hashed.is_some()
+        || incoming.hour_type.is_some()
+        || incoming.sign_in.is_some()
+        || !incoming.sign_out.is_undefined()
+    {
+        let sid_hashed = incoming.sid_hashed.as_ref().unwrap_or(&old.sid_hashed);
+        let kind = incoming.hour_type.as_ref().unwrap_or(&old.hour_type);
+        let sign_in = incoming.sign_in.unwrap_or(old.sign_in);
+        let sign_out = incoming.sign_out.value().copied().or(old.sign_out);
+
+        let policy = kind.session_policy(&pg).await?;
+
+        if policy.overlap != OverlapPolicy::Allow
+            && overlap::overlaps(sid_hashed, kind, sign_in, sign_out, Some(&old.id), &pg).await?
+        {
+            if policy.overlap == OverlapPolicy::Reject {
+                return Err(UpdateError::overlap());
+            }
+
+            flagged = flagged.or(Some(true));
+        }
+    }
 
     let new = sqlx::query_as::<_, Record>(
         r#"
@@ -298,7 +368,9 @@ pub(super) async fn update(
             sid_hashed = COALESCE($2, sid_hashed),
             hour_type = COALESCE($3, hour_type),
             sign_in = COALESCE($4, sign_in),
-            sign_out = COALESCE($5, sign_out)
+            sign_out = COALESCE($5, sign_out),
+            flagged = COALESCE($6, flagged),
+            multi_day = COALESCE($7, multi_day)
         WHERE id = $1
         RETURNING *
         "#,
@@ -308,6 +380,8 @@ pub(super) async fn update(
     .bind(incoming.hour_type)
     .bind(incoming.sign_in)
     .bind(incoming.sign_out.value())
+    .bind(flagged)
+    .bind(incoming.multi_day)
     .fetch_one(&pg) // checked for existence above
     .await?;
 
@@ -333,17 +407,10 @@ pub(super) async fn delete(
     claims: jwt::Claims,
     pg: PgPool,
 ) -> Result<DeleteResponse, DeleteError> {
-    let record = sqlx::query_as::<_, Record>(
-        r#"
-        DELETE FROM records
-        WHERE id = $1
-        RETURNING *
-        "#,
-    )
-    .bind(entry_id)
-    .fetch_optional(&pg)
-    .await?
-    .ok_or(DeleteError::not_found())?;
+    let record = trash::trash_records(&[entry_id], &claims.sub, &pg)
+        .await?
+        .pop()
+        .ok_or(DeleteError::not_found())?;
 
     let record_telemeter = record.clone();
     tokio::spawn(async move {
@@ -0,0 +1,158 @@
+use rand::{RngCore, rng};
+use sha2::{Digest, Sha256};
+
+use crate::prelude::*;
+
+/// A student's phone, enrolled once by an admin so the student can sign
+/// themselves in and out
+#[derive(Object, Debug, Clone)]
+pub(super) struct Device {
+    id: String,
+    sid_hashed: String,
+    name: Option<String>,
+    enrolled_by: Option<String>,
+    enrolled_at: chrono::DateTime<Utc>,
+    last_used_at: Option<chrono::DateTime<Utc>>,
+    /// Null until revoked. Revoked devices can no longer swipe.
+    revoked_at: Option<chrono::DateTime<Utc>>,
+}
+
+#[derive(Object, Debug)]
+#[oai(rename = "DeviceEnrollRequest")]
+pub(super) struct EnrollRequest {
+    sid_hashed: String,
+    /// Shown to admins to tell a student's devices apart
+    name: Option<String>,
+}
+
+#[derive(Object)]
+#[oai(rename = "DeviceEnrollResponse")]
+pub(super) struct EnrollResponse {
+    device: Device,
+    /// Device credential. Only ever returned here, store it on the device.
+    token: String,
+}
+
+#[derive(Object)]
+#[oai(rename = "DeviceQueryManyResponse")]
+pub(super) struct QueryManyResponse {
+    devices: Vec<Device>,
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum DeviceError {
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    /// No student or unrevoked device with the given ID exists
+    #[oai(status = 404)]
+    #[construct(student, "Student not found")]
+    #[construct(device, "Device not found")]
+    NotFound(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+fn hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn query_many(
+    sid_hashed: Option<String>,
+    pg: PgPool,
+) -> Result<QueryManyResponse, DeviceError> {
+    let devices = sqlx::query_as!(
+        Device,
+        r#"
+        SELECT id, sid_hashed, name, enrolled_by, enrolled_at, last_used_at, revoked_at
+        FROM student_devices
+        WHERE ($1::text IS NULL OR sid_hashed = $1)
+        ORDER BY enrolled_at DESC
+        "#,
+        sid_hashed,
+    )
+    .fetch_all(&pg)
+    .await?;
+
+    Ok(QueryManyResponse { devices })
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn enroll(
+    EnrollRequest { sid_hashed, name }: EnrollRequest,
+    admin_id: String,
+    pg: PgPool,
+) -> Result<EnrollResponse, DeviceError> {
+    let mut token = [0u8; 32];
+    rng().fill_bytes(&mut token);
+    let token = hex::encode(token);
+
+    let device = sqlx::query_as!(
+        Device,
+        r#"
+        INSERT INTO student_devices (id, sid_hashed, token_hash, name, enrolled_by)
+        SELECT $1, $2, $3, $4, $5
+        WHERE EXISTS (
+            SELECT 1
+            FROM students s
+            WHERE s.id_hashed = $2
+        )
+        RETURNING id, sid_hashed, name, enrolled_by, enrolled_at, last_used_at, revoked_at
+        "#,
+        cuid2(),
+        sid_hashed,
+        hash(&token),
+        name,
+        admin_id,
+    )
+    .fetch_optional(&pg)
+    .await?
+    .ok_or(DeviceError::student())?;
+
+    Ok(EnrollResponse { device, token })
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn revoke(id: String, pg: PgPool) -> Result<Device, DeviceError> {
+    sqlx::query_as!(
+        Device,
+        r#"
+        UPDATE student_devices
+        SET revoked_at = NOW()
+        WHERE id = $1 AND revoked_at IS NULL
+        RETURNING id, sid_hashed, name, enrolled_by, enrolled_at, last_used_at, revoked_at
+        "#,
+        id,
+    )
+    .fetch_optional(&pg)
+    .await?
+    .ok_or(DeviceError::device())
+}
+
+/// Look up the unrevoked device holding `token`, returning its ID and the
+/// student it belongs to
+pub(super) async fn authenticate(
+    token: &str,
+    pg: &PgPool,
+) -> Result<Option<(String, String)>, sqlx::Error> {
+    let device = sqlx::query!(
+        r#"
+        UPDATE student_devices
+        SET last_used_at = NOW()
+        WHERE token_hash = $1 AND revoked_at IS NULL
+        RETURNING id, sid_hashed
+        "#,
+        hash(token),
+    )
+    .fetch_optional(pg)
+    .await?;
+
+    Ok(device.map(|d| (d.id, d.sid_hashed)))
+}
@@ -0,0 +1,243 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{cache::Cached, prelude::*};
+
+/// Mean radius of the Earth, in meters
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// Locations less accurate than this, in meters, are treated as outside every
+/// geofence. Otherwise a spoofed, huge accuracy would overlap any fence.
+const MAX_ACCURACY: f64 = 100.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Enum, sqlx::Type)]
+#[oai(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "geofence_policy", rename_all = "snake_case")]
+pub(crate) enum GeofencePolicy {
+    /// Reject swipes from outside every geofence, or without a location
+    Reject,
+    /// Accept swipes from outside every geofence, or without a location, but
+    /// flag them for review
+    Flag,
+    /// Don't check locations at all
+    Ignore,
+}
+
+/// A device's reported position, as given by the browser's geolocation API
+#[derive(Object, Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct Location {
+    /// In degrees
+    pub latitude: f64,
+    /// In degrees
+    pub longitude: f64,
+    /// Radius of uncertainty, in meters
+    pub accuracy: f64,
+}
+
+#[derive(Object, Debug, Clone, Copy)]
+pub(super) struct Geofence {
+    /// In degrees
+    pub latitude: f64,
+    /// In degrees
+    pub longitude: f64,
+    /// In meters, must be positive
+    pub radius: f64,
+}
+
+#[derive(Object, Debug, Clone)]
+pub(super) struct GeofenceConfig {
+    pub policy: GeofencePolicy,
+    /// A swipe is inside if it is inside any of these. If empty, every swipe
+    /// is inside.
+    pub geofences: Vec<Geofence>,
+}
+
+impl Default for GeofenceConfig {
+    fn default() -> Self {
+        Self {
+            policy: GeofencePolicy::Ignore,
+            geofences: vec![],
+        }
+    }
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum GeofenceError {
+    #[oai(status = 400)]
+    #[construct(
+        invalid,
+        "Latitude must be within ±90, longitude within ±180, and radius must be positive"
+    )]
+    BadRequest(PlainText<String>),
+
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+impl Geofence {
+    fn valid(&self) -> bool {
+        (-90.0..=90.0).contains(&self.latitude)
+            && (-180.0..=180.0).contains(&self.longitude)
+            && self.radius > 0.0
+    }
+
+    /// Great-circle distance from the center of the fence, in meters
+    fn distance(&self, location: &Location) -> f64 {
+        let (lat1, lat2) = (self.latitude.to_radians(), location.latitude.to_radians());
+        let dlat = lat2 - lat1;
+        let dlon = (location.longitude - self.longitude).to_radians();
+
+        let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+
+        2.0 * EARTH_RADIUS * a.sqrt().asin()
+    }
+
+    /// Whether `location` could be inside the fence, given its accuracy
+    fn contains(&self, location: &Location) -> bool {
+        self.distance(location) <= self.radius + location.accuracy
+    }
+}
+
+impl GeofenceConfig {
+    /// Whether a swipe from `location` is outside the configured geofences.
+    ///
+    /// Always false under the `ignore` policy.
+    pub(super) fn outside(&self, location: Option<&Location>) -> bool {
+        if self.policy == GeofencePolicy::Ignore || self.geofences.is_empty() {
+            return false;
+        }
+
+        let Some(location) = location else {
+            return true;
+        };
+
+        if !location.accuracy.is_finite() || !(0.0..=MAX_ACCURACY).contains(&location.accuracy) {
+            return true;
+        }
+
+        !self.geofences.iter().any(|fence| fence.contains(location))
+    }
+}
+
+/// Geofence configuration of every hour type that has any
+async fn configs(pg: &PgPool) -> Result<Arc<HashMap<HourType, GeofenceConfig>>, sqlx::Error> {
+    static CACHE: Cached<HashMap<HourType, GeofenceConfig>> = Cached::new();
+
+    CACHE
+        .get(|| async {
+            let mut configs = sqlx::query!(
+                r#"
+                SELECT kind AS "kind: HourType", policy AS "policy: GeofencePolicy"
+                FROM geofence_config
+                "#,
+            )
+            .fetch_all(pg)
+            .await?
+            .into_iter()
+            .map(|row| {
+                (
+                    row.kind,
+                    GeofenceConfig {
+                        policy: row.policy,
+                        geofences: vec![],
+                    },
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+            let geofences = sqlx::query!(
+                r#"
+                SELECT hour_type AS "hour_type: HourType", latitude, longitude, radius
+                FROM geofences
+                "#,
+            )
+            .fetch_all(pg)
+            .await?;
+
+            // geofences without a policy are ignored, as if there were none
+            for row in geofences {
+                if let Some(config) = configs.get_mut(&row.hour_type) {
+                    config.geofences.push(Geofence {
+                        latitude: row.latitude,
+                        longitude: row.longitude,
+                        radius: row.radius,
+                    });
+                }
+            }
+
+            Ok(configs)
+        })
+        .await
+}
+
+impl HourType {
+    pub(super) async fn geofence(&self, pg: &PgPool) -> Result<GeofenceConfig, sqlx::Error> {
+        Ok(configs(pg).await?.get(self).cloned().unwrap_or_default())
+    }
+
+    /// Replace the geofence policy and every geofence for this hour type
+    pub(super) async fn update_geofence(
+        &self,
+        GeofenceConfig { policy, geofences }: GeofenceConfig,
+        pg: PgPool,
+    ) -> Result<(), GeofenceError> {
+        if !geofences.iter().all(Geofence::valid) {
+            return Err(GeofenceError::invalid());
+        }
+
+        let mut tx = pg.begin().await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO geofence_config (kind, policy)
+            VALUES ($1, $2)
+            ON CONFLICT (kind) DO UPDATE
+            SET policy = EXCLUDED.policy
+            "#,
+            self.as_str(),
+            policy as GeofencePolicy,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"DELETE FROM geofences WHERE hour_type = $1"#,
+            self.as_str(),
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for Geofence {
+            latitude,
+            longitude,
+            radius,
+        } in geofences
+        {
+            sqlx::query!(
+                r#"
+                INSERT INTO geofences (id, hour_type, latitude, longitude, radius)
+                VALUES ($1, $2, $3, $4, $5)
+                "#,
+                cuid2(),
+                self.as_str(),
+                latitude,
+                longitude,
+                radius,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::HashSet;
+
+use super::{overlap, session::OverlapPolicy};
+use crate::{prelude::*, roster::hour_type::HourTypeError};
+
+#[derive(Object, Debug)]
+#[oai(rename = "GroupSignInRequest")]
+pub(super) struct SignInRequest {
+    hour_type: HourType,
+    /// Every student to sign in
+    sid_hashed: Vec<String>,
+    /// When the students arrived. Defaults to now, must not be in the future.
+    at: Option<chrono::DateTime<Utc>>,
+}
+
+#[derive(Object, Debug)]
+#[oai(rename = "GroupSignOutRequest")]
+pub(super) struct SignOutRequest {
+    hour_type: HourType,
+    /// When the session ended. Defaults to now, must not be in the future.
+    at: Option<chrono::DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Object)]
+pub(crate) struct GroupRecord {
+    pub(crate) sid_hashed: String,
+    pub(crate) record_id: String,
+}
+
+#[derive(Object)]
+#[oai(rename = "GroupSignInResponse")]
+pub(super) struct SignInResponse {
+    signed_in: Vec<GroupRecord>,
+    /// Students who were already signed in, and were left as they were
+    already: Vec<String>,
+    /// Students who don't exist
+    unknown: Vec<String>,
+    /// Students whose new record would overlap one of theirs, which the hour
+    /// type's overlap policy rejects
+    overlapping: Vec<String>,
+}
+
+#[derive(Object)]
+#[oai(rename = "GroupSignOutResponse")]
+pub(super) struct SignOutResponse {
+    signed_out: Vec<GroupRecord>,
+    /// Records signed in at or after `at`, which were left open
+    skipped: Vec<GroupRecord>,
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError, HourTypeError)]
+pub(super) enum Error {
+    /// The hour type is not allowed at this time, no students were given, or
+    /// `at` is in the future
+    #[oai(status = 400)]
+    #[construct(hour_type(HourType), "{source} hours are not allowed right now")]
+    #[construct(empty, "No students given")]
+    #[construct(at, "at must not be in the future")]
+    BadRequest(PlainText<String>),
+
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+fn resolve_at(at: Option<chrono::DateTime<Utc>>) -> Result<chrono::DateTime<Utc>, Error> {
+    let now = Utc::now();
+
+    match at {
+        Some(at) if at > now => Err(Error::at()),
+        Some(at) => Ok(at),
+        None => Ok(now),
+    }
+}
+
+/// Sign in every given student at once, e.g. when a bus arrives
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn sign_in(
+    SignInRequest {
+        hour_type,
+        mut sid_hashed,
+        at,
+    }: SignInRequest,
+    admin_id: String,
+    pg: PgPool,
+) -> Result<SignInResponse, Error> {
+    sid_hashed.sort();
+    sid_hashed.dedup();
+
+    if sid_hashed.is_empty() {
+        return Err(Error::empty());
+    }
+
+    let at = resolve_at(at)?;
+
+    if !hour_type.allowed(&pg).await? {
+        return Err(Error::hour_type(hour_type.clone()));
+    }
+
+    let policy = hour_type.session_policy(&pg).await?;
+
+    let mut tx = pg.begin().await?;
+
+    let open = sqlx::query!(
+        r#"
+        SELECT sid_hashed, sign_in, multi_day
+        FROM records
+        WHERE hour_type = $1
+            AND sign_out IS NULL
+            AND sid_hashed = ANY($2)
+        FOR UPDATE
+        "#,
+        hour_type.as_str(),
+        &sid_hashed,
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let already = open
+        .into_iter()
+        .filter(|r| policy.open_at(r.multi_day, r.sign_in, at))
+        .map(|r| r.sid_hashed)
+        .collect::<HashSet<_>>();
+
+    let mut signed_in = vec![];
+    let mut unknown = vec![];
+    let mut overlapping = vec![];
+
+    for sid_hashed in sid_hashed.iter().filter(|s| !already.contains(*s)) {
+        // `at` may be in the past, so check it like any other manual record
+        let overlaps = policy.overlap != OverlapPolicy::Allow
+            && overlap::overlaps(sid_hashed, &hour_type, at, None, None, &mut *tx).await?;
+
+        if overlaps && policy.overlap == OverlapPolicy::Reject {
+            overlapping.push(sid_hashed.clone());
+            continue;
+        }
+
+        let record_id = cuid2();
+
+        let q = sqlx::query!(
+            r#"
+            INSERT INTO records (id, sid_hashed, hour_type, sign_in, flagged)
+            SELECT $1, $2, $3, $4, $5
+            WHERE EXISTS (
+                SELECT 1
+                FROM students s
+                WHERE s.id_hashed = $2
+            )
+            "#,
+            record_id,
+            sid_hashed,
+            hour_type.as_str(),
+            at,
+            overlaps,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if q.rows_affected() == 0 {
+            unknown.push(sid_hashed.clone());
+        } else {
+            signed_in.push(GroupRecord {
+                sid_hashed: sid_hashed.clone(),
+                record_id,
+            });
+        }
+    }
+
+    tx.commit().await?;
+
+    if !signed_in.is_empty() {
+        let signed_in = signed_in.clone();
+        tokio::spawn(async move {
+            telemeter(
+                GroupSignIn {
+                    admin_id,
+                    hour_type,
+                    at,
+                    signed_in,
+                },
+                &pg,
+            )
+            .await
+            .log();
+        });
+    }
+
+    Ok(SignInResponse {
+        signed_in,
+        already: already.into_iter().collect(),
+        unknown,
+        overlapping,
+    })
+}
+
+/// Sign out every student signed in to an hour type at once, e.g. at the end
+/// of a meeting.
+///
+/// Records that are no longer open at `at` under the hour type's session
+/// policy are left for auto-close. Records signed in at or after `at` are left
+/// open and returned as skipped.
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn sign_out(
+    SignOutRequest { hour_type, at }: SignOutRequest,
+    admin_id: String,
+    pg: PgPool,
+) -> Result<SignOutResponse, Error> {
+    let at = resolve_at(at)?;
+    let policy = hour_type.session_policy(&pg).await?;
+
+    let mut tx = pg.begin().await?;
+
+    let open = sqlx::query!(
+        r#"
+        SELECT id, sid_hashed, sign_in, multi_day
+        FROM records
+        WHERE hour_type = $1 AND sign_out IS NULL
+        FOR UPDATE
+        "#,
+        hour_type.as_str(),
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let (signed_out, skipped): (Vec<_>, Vec<_>) = open
+        .into_iter()
+        .filter(|r| policy.open_at(r.multi_day, r.sign_in, at))
+        .partition(|r| r.sign_in < at);
+
+    let [signed_out, skipped] = [signed_out, skipped].map(|records| {
+        records
+            .into_iter()
+            .map(|r| GroupRecord {
+                sid_hashed: r.sid_hashed,
+                record_id: r.id,
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let ids = signed_out
+        .iter()
+        .map(|r| r.record_id.clone())
+        .collect::<Vec<_>>();
+
+    sqlx::query!(
+        r#"
+        UPDATE records
+        SET sign_out = $2
+        WHERE id = ANY($1)
+        "#,
+        &ids,
+        at,
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    if !signed_out.is_empty() {
+        let signed_out = signed_out.clone();
+        tokio::spawn(async move {
+            telemeter(
+                GroupSignOut {
+                    admin_id,
+                    hour_type,
+                    at,
+                    signed_out,
+                },
+                &pg,
+            )
+            .await
+            .log();
+        });
+    }
+
+    Ok(SignOutResponse {
+        signed_out,
+        skipped,
+    })
+}
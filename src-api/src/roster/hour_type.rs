@@ -1,7 +1,18 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, LazyLock},
+};
+
 use chrono::{Datelike, NaiveDate};
-use strum::{Display, EnumString, VariantArray};
+use poem_openapi::NewType;
+use regex::Regex;
+
+use crate::{cache::Cached, prelude::*, season::calendar::Calendar};
 
-use crate::prelude::*;
+const BUILD: &str = "build";
+const LEARNING: &str = "learning";
+const OFFSEASON: &str = "offseason";
 
 #[derive(ApiResponse, ApiError)]
 #[from(JwtVerifyError, PermissionDeniedError)]
@@ -17,271 +28,373 @@ pub(crate) enum HourTypeError {
     InternalServerError(PlainText<String>),
 }
 
-#[derive(Object, Debug, Clone, Copy)]
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum HourTypeConfigError {
+    #[oai(status = 400)]
+    #[construct(id, "IDs may only contain lowercase letters, digits, '-' and '_'")]
+    #[construct(
+        info,
+        "Name must not be empty, color must look like #a1b2c3, and goal must be nonnegative"
+    )]
+    BadRequest(PlainText<String>),
+
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    /// No hour type with the given ID exists
+    #[oai(status = 404)]
+    #[construct("Hour type not found")]
+    NotFound(PlainText<String>),
+
+    /// The hour type already exists, or is still referenced by records,
+    /// meetings, adjustments or TOTPs and can't be deleted
+    #[oai(status = 409)]
+    #[construct(exists, "An hour type with this ID already exists")]
+    #[construct(in_use, "Hour type is still in use")]
+    Conflict(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+#[derive(Object, Debug, Clone)]
 pub(super) struct HourTypeInfo {
+    /// Display name, e.g. "Build Season"
+    pub name: String,
+    /// Hex color, e.g. "#ef4444"
+    pub color: String,
     /// Year is ignored, filtering is applied based on month and day only
     ///
-    /// If null, automatic filtering is applied
+    /// If null, falls back to the season calendar
     pub begins: Option<chrono::NaiveDate>,
     /// Year is ignored, filtering is applied based on month and day only
     ///
-    /// If null, automatic filtering is applied
+    /// If null, falls back to the season calendar
     pub ends: Option<chrono::NaiveDate>,
     /// In hours, can be fractional, must be nonnegative
     pub goal: f64,
 }
 
-impl Default for HourTypeInfo {
-    fn default() -> Self {
-        Self {
-            begins: None,
-            ends: None,
-            goal: 0.0,
-        }
-    }
+#[derive(Object, Debug, Clone)]
+pub(super) struct HourTypeDetails {
+    pub id: HourType,
+    #[oai(flatten)]
+    pub info: HourTypeInfo,
 }
 
+/// ID of an hour type from the `hour_types` table, a short slug such as
+/// `build`
 #[derive(
-    Clone,
-    Copy,
-    Debug,
-    PartialEq,
-    Eq,
-    Hash,
-    Serialize,
-    Deserialize,
-    Enum,
-    EnumString,
-    VariantArray,
-    Display,
-    sqlx::Type,
+    Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, NewType, sqlx::Type,
 )]
-#[oai(rename_all = "lowercase")]
-#[strum(serialize_all = "lowercase")]
-#[serde(rename_all = "lowercase")]
-#[sqlx(type_name = "hour_type", rename_all = "lowercase")]
-pub(crate) enum HourType {
-    Build,
-    Learning,
-    Demo,
-    Offseason,
-}
+#[serde(transparent)]
+#[sqlx(transparent)]
+pub(crate) struct HourType(String);
 
-fn kickoff_day() -> NaiveDate {
-    // who tf knows when this will change but until first decides to give me an api
-    // for ts its staying this way
-    //
-    // ok, so, to find the date of kickoff, we can use the fact that its always the
-    // first saturday **unless** Jan 1st is a Thursday, Friday, or Saturday,
-    // in which case its the second saturday
+impl fmt::Display for HourType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
 
-    // get new years day information
-    let today = Local::now().date_naive();
-    let year = today.year();
-    let nyd = chrono::NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
-    let nydotw = nyd.weekday();
+impl HourType {
+    pub(crate) fn as_str(&self) -> &str {
+        &self.0
+    }
 
-    // postpone iff nyd is thurs, fri, sat
-    let postpone = nydotw.num_days_from_sunday() >= 4;
-    let postpone = if postpone { 7 } else { 0 };
+    fn valid_id(&self) -> bool {
+        static ID: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[a-z0-9_-]+$").unwrap());
 
-    // calculate kickoff day
-    let days2sat = 6 - nydotw.days_since(chrono::Weekday::Sun);
+        ID.is_match(&self.0)
+    }
 
-    nyd + chrono::Duration::days(i64::from(days2sat) + postpone)
-}
+    /// Every hour type, in order of ID
+    pub(crate) async fn all(pg: &PgPool) -> Result<Vec<HourType>, sqlx::Error> {
+        Ok(infos(pg).await?.keys().cloned().collect())
+    }
 
-impl HourType {
-    /// Check if this hour type is allowed in the given 1-based month.
-    #[allow(clippy::too_many_lines)]
-    pub(crate) async fn allowed(self, pg: &PgPool) -> Result<bool, HourTypeError> {
-        let today = Local::now().date_naive();
-        let year = Local::now().year();
+    async fn info(&self, pg: &PgPool) -> Result<Option<HourTypeInfo>, sqlx::Error> {
+        Ok(infos(pg).await?.get(self).cloned())
+    }
 
-        let default_build_start = kickoff_day();
-        let default_build_end = chrono::NaiveDate::from_ymd_opt(year, 4, 30).unwrap();
+    /// The dates in `calendar`'s year between which this hour type is allowed,
+    /// inclusive.
+    ///
+    /// Unset dates fall back to the calendar for the built-in hour types, and
+    /// otherwise cover the whole year.
+    fn window(&self, info: &HourTypeInfo, calendar: &Calendar) -> (NaiveDate, NaiveDate) {
+        let year = calendar.year;
+        let this_year = |date: Option<NaiveDate>| date.and_then(|n| n.with_year(year));
+
+        let start = this_year(info.begins).unwrap_or_else(|| match self.as_str() {
+            BUILD => calendar.kickoff,
+            LEARNING => calendar.learning_starts,
+            OFFSEASON => calendar.offseason_starts,
+            _ => NaiveDate::from_ymd_opt(year, 1, 1).unwrap(),
+        });
+
+        let end = this_year(info.ends).unwrap_or_else(|| match self.as_str() {
+            BUILD => calendar.build_ends,
+            LEARNING => calendar.kickoff - chrono::Duration::days(1),
+            OFFSEASON => calendar.offseason_ends,
+            _ => NaiveDate::from_ymd_opt(year, 12, 31).unwrap(),
+        });
+
+        (start, end)
+    }
 
-        let default_learning_end = async |pg: &PgPool| {
-            let build_info = HourType::Build.query(pg.clone()).await?;
+    /// When every hour type is allowed in `calendar`'s year
+    pub(crate) async fn windows(
+        calendar: &Calendar,
+        pg: &PgPool,
+    ) -> Result<Vec<(HourType, (NaiveDate, NaiveDate))>, sqlx::Error> {
+        Ok(query_many(pg.clone())
+            .await?
+            .into_iter()
+            .map(|HourTypeDetails { id, info }| {
+                let window = id.window(&info, calendar);
+                (id, window)
+            })
+            .collect())
+    }
 
-            let build_start = build_info
-                .begins
-                .and_then(|n| n.with_year(year))
-                .unwrap_or(default_build_start);
+    /// Check if this hour type is allowed today. Hour types that don't exist
+    /// never are.
+    pub(crate) async fn allowed(&self, pg: &PgPool) -> Result<bool, HourTypeError> {
+        self.allowed_at(Local::now().date_naive(), pg).await
+    }
 
-            Ok::<_, HourTypeError>(build_start - chrono::Duration::days(1))
+    /// Check if this hour type is allowed on `date`, in server's local time
+    pub(crate) async fn allowed_at(
+        &self,
+        date: NaiveDate,
+        pg: &PgPool,
+    ) -> Result<bool, HourTypeError> {
+        let Some(info) = self.info(pg).await? else {
+            return Ok(false);
         };
 
-        let default_offseason_start = async |pg: &PgPool, build_info: &mut Option<HourTypeInfo>| {
-            let build_info = match build_info {
-                Some(info) => *info,
-                None => {
-                    let info = HourType::Build.query(pg.clone()).await?;
-                    *build_info = Some(info);
-                    info
-                }
-            };
-
-            let build_end = build_info
-                .ends
-                .and_then(|n| n.with_year(year))
-                .unwrap_or(default_build_end);
-
-            Ok::<_, HourTypeError>(build_end + chrono::Duration::days(1))
-        };
+        let calendar = Calendar::resolve(date.year(), pg).await?;
+        let (start, end) = self.window(&info, &calendar);
 
-        let default_offseason_end = async |pg: &PgPool, build_info: &mut Option<HourTypeInfo>| {
-            let build_info = match build_info {
-                Some(info) => *info,
-                None => {
-                    let info = HourType::Build.query(pg.clone()).await?;
-                    *build_info = Some(info);
-                    info
-                }
-            };
-
-            let build_start = build_info
-                .begins
-                .and_then(|n| n.with_year(year))
-                .unwrap_or(default_build_start);
-
-            Ok::<_, HourTypeError>(build_start - chrono::Duration::days(1))
-        };
+        if start <= end {
+            Ok(date >= start && date <= end)
+        } else {
+            // range wraps end of year
+            Ok(date >= start || date <= end)
+        }
+    }
 
-        let (start, end) = match self {
-            HourType::Build => {
-                let info = self.query(pg.clone()).await?;
+    fn validate(info: &HourTypeInfo) -> Result<(), HourTypeConfigError> {
+        static COLOR: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^#[0-9a-fA-F]{6}$").unwrap());
 
-                let start = info
-                    .begins
-                    .and_then(|n| n.with_year(year))
-                    .unwrap_or(default_build_start);
+        if info.name.trim().is_empty() || !COLOR.is_match(&info.color) || info.goal < 0.0 {
+            return Err(HourTypeConfigError::info());
+        }
 
-                let end = info
-                    .ends
-                    .and_then(|n| n.with_year(year))
-                    .unwrap_or(default_build_end);
+        Ok(())
+    }
 
-                (start, end)
-            }
-            HourType::Learning => {
-                let info = self.query(pg.clone()).await?;
-                let default_start = chrono::NaiveDate::from_ymd_opt(year, 9, 1).unwrap();
+    pub(super) async fn create(
+        HourTypeDetails { id, info }: HourTypeDetails,
+        pg: PgPool,
+    ) -> Result<(), HourTypeConfigError> {
+        if !id.valid_id() {
+            return Err(HourTypeConfigError::id());
+        }
 
-                let start = info
-                    .begins
-                    .and_then(|n| n.with_year(year))
-                    .unwrap_or(default_start);
+        Self::validate(&info)?;
 
-                let end = match info.ends.and_then(|n| n.with_year(year)) {
-                    Some(n) => n,
-                    None => default_learning_end(pg).await?,
-                };
+        let mut tx = pg.begin().await?;
 
-                (start, end)
-            }
-            HourType::Demo => {
-                let info = self.query(pg.clone()).await?;
-                let default_start = chrono::NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
-                let default_end = chrono::NaiveDate::from_ymd_opt(year, 12, 31).unwrap();
-
-                let start = info
-                    .begins
-                    .and_then(|n| n.with_year(year))
-                    .unwrap_or(default_start);
-
-                let end = info
-                    .ends
-                    .and_then(|n| n.with_year(year))
-                    .unwrap_or(default_end);
-
-                (start, end)
-            }
-            HourType::Offseason => {
-                let info = self.query(pg.clone()).await?;
-                let mut build_info = None;
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO hour_types (id, name, color, begins, ends, goal)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (id) DO NOTHING
+            "#,
+            id.as_str(),
+            info.name,
+            info.color,
+            info.begins,
+            info.ends,
+            info.goal,
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
 
-                let start = match info.begins.and_then(|n| n.with_year(year)) {
-                    Some(n) => n,
-                    None => default_offseason_start(pg, &mut build_info).await?,
-                };
+        if inserted == 0 {
+            return Err(HourTypeConfigError::exists());
+        }
 
-                let end = match info.ends.and_then(|n| n.with_year(year)) {
-                    Some(n) => n,
-                    None => default_offseason_end(pg, &mut build_info).await?,
-                };
+        // everything else falls back to defaults when unconfigured
+        sqlx::query!(
+            r#"INSERT INTO auto_close_config (kind) VALUES ($1)"#,
+            id.as_str(),
+        )
+        .execute(&mut *tx)
+        .await?;
 
-                (start, end)
-            }
-        };
+        tx.commit().await?;
 
-        if start <= end {
-            Ok(today >= start && today <= end)
-        } else {
-            // range wraps end of year
-            Ok(today >= start || today <= end)
-        }
+        Ok(())
     }
 
     pub(super) async fn update(
         &self,
-        HourTypeInfo {
+        info: HourTypeInfo,
+        pg: PgPool,
+    ) -> Result<(), HourTypeConfigError> {
+        Self::validate(&info)?;
+
+        let HourTypeInfo {
+            name,
+            color,
             begins,
             ends,
-            goal: requirement,
-        }: HourTypeInfo,
-        pg: PgPool,
-    ) -> Result<(), HourTypeError> {
-        sqlx::query!(
+            goal,
+        } = info;
+
+        let updated = sqlx::query!(
             r#"
-            UPDATE hour_config
-            SET begins = $2, ends = $3, goal = $4
-            WHERE kind = $1
+            UPDATE hour_types
+            SET name = $2, color = $3, begins = $4, ends = $5, goal = $6
+            WHERE id = $1
             "#,
-            *self as HourType,
+            self.as_str(),
+            name,
+            color,
             begins,
             ends,
-            requirement,
+            goal,
         )
         .execute(&pg)
-        .await?;
+        .await?
+        .rows_affected();
+
+        if updated == 0 {
+            return Err(HourTypeConfigError::not_found());
+        }
 
         Ok(())
     }
 
-    pub(super) async fn query(&self, pg: PgPool) -> Result<HourTypeInfo, HourTypeError> {
-        let res = sqlx::query_as!(
-            HourTypeInfo,
+    pub(super) async fn delete(&self, pg: PgPool) -> Result<(), HourTypeConfigError> {
+        let deleted = sqlx::query!(r#"DELETE FROM hour_types WHERE id = $1"#, self.as_str())
+            .execute(&pg)
+            .await;
+
+        match deleted {
+            Ok(res) if res.rows_affected() == 0 => Err(HourTypeConfigError::not_found()),
+            Ok(_) => Ok(()),
+            Err(sqlx::Error::Database(err)) if err.is_foreign_key_violation() => {
+                Err(HourTypeConfigError::in_use())
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub(super) async fn query(&self, pg: PgPool) -> Result<HourTypeInfo, HourTypeConfigError> {
+        self.info(&pg)
+            .await?
+            .ok_or(HourTypeConfigError::not_found())
+    }
+
+    /// The goal for this hour type during `season`. Seasons that have been
+    /// archived keep the goal they had at rollover, others use the current
+    /// goal.
+    pub(super) async fn goal(
+        &self,
+        season: Option<String>,
+        pg: PgPool,
+    ) -> Result<f64, HourTypeConfigError> {
+        self.season_goal(season.as_deref(), &pg)
+            .await?
+            .ok_or(HourTypeConfigError::not_found())
+    }
+
+    /// Like [`HourType::goal`], but None if the hour type doesn't exist
+    pub(super) async fn season_goal(
+        &self,
+        season: Option<&str>,
+        pg: &PgPool,
+    ) -> Result<Option<f64>, sqlx::Error> {
+        sqlx::query_scalar!(
             r#"
-            SELECT begins, ends, goal
-            FROM hour_config
-            WHERE kind = $1
+            SELECT COALESCE(sg.goal, h.goal) AS "goal!"
+            FROM hour_types h
+            LEFT JOIN season_goals sg ON sg.hour_type = h.id AND sg.season_id = $2
+            WHERE h.id = $1
             "#,
-            *self as HourType,
+            self.as_str(),
+            season,
         )
-        .fetch_one(&pg)
-        .await?;
-
-        Ok(res)
+        .fetch_optional(pg)
+        .await
     }
+}
 
-    pub(super) async fn goal(&self, pg: PgPool) -> Result<f64, HourTypeError> {
-        let res = sqlx::query!(
-            r#"SELECT goal FROM hour_config WHERE kind = $1"#,
-            *self as HourType
-        )
-        .fetch_one(&pg)
-        .await?;
+/// Every hour type's configuration, by ID
+async fn infos(pg: &PgPool) -> Result<Arc<BTreeMap<HourType, HourTypeInfo>>, sqlx::Error> {
+    static CACHE: Cached<BTreeMap<HourType, HourTypeInfo>> = Cached::new();
+
+    CACHE
+        .get(|| async {
+            let rows = sqlx::query!(
+                r#"
+                SELECT id AS "id: HourType", name, color, begins, ends, goal
+                FROM hour_types
+                "#
+            )
+            .fetch_all(pg)
+            .await?;
+
+            Ok(rows
+                .into_iter()
+                .map(|row| {
+                    (
+                        row.id,
+                        HourTypeInfo {
+                            name: row.name,
+                            color: row.color,
+                            begins: row.begins,
+                            ends: row.ends,
+                            goal: row.goal,
+                        },
+                    )
+                })
+                .collect())
+        })
+        .await
+}
 
-        Ok(res.goal)
-    }
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn query_many(pg: PgPool) -> Result<Vec<HourTypeDetails>, sqlx::Error> {
+    Ok(infos(&pg)
+        .await?
+        .iter()
+        .map(|(id, info)| HourTypeDetails {
+            id: id.clone(),
+            info: info.clone(),
+        })
+        .collect())
 }
 
+/// Hour types that can be swiped right now, i.e. are allowed today.
+///
+/// Hour types that are blacked out or outside their swipe windows are still
+/// listed, since students signed in to them must be able to swipe out.
 #[tracing::instrument]
 pub(crate) async fn allowed(pool: &PgPool) -> Result<Vec<HourType>, HourTypeError> {
-    let mut allowed = Vec::with_capacity(HourType::VARIANTS.len());
+    let mut allowed = vec![];
 
-    for &kind in HourType::VARIANTS {
+    for kind in HourType::all(pool).await? {
         if kind.allowed(pool).await? {
             allowed.push(kind);
         }
@@ -0,0 +1,226 @@
+use poem_openapi::{SecurityScheme, auth::Bearer};
+use rand::{RngCore, rng};
+use sha2::{Digest, Sha256};
+use totp_rs::Secret;
+
+use super::hour_type::HourTypeError;
+use crate::prelude::*;
+
+/// A kiosk's own credential, from registration. It can only fetch the
+/// kiosk's TOTP secrets and allowed hour types; swipes are authenticated by
+/// TOTP as usual, with the kiosk's ID as `issuer`.
+#[derive(SecurityScheme)]
+#[oai(ty = "bearer", rename = "KioskToken")]
+pub(super) struct KioskToken(Bearer);
+
+/// A device that takes swipes, on behalf of the admin who registered it
+#[derive(Object, Debug, Clone)]
+pub(super) struct Kiosk {
+    /// Used as `issuer` in swipes, and as the TOTP `account_name`
+    id: String,
+    name: String,
+    registered_by: Option<String>,
+    registered_at: chrono::DateTime<Utc>,
+    last_seen_at: Option<chrono::DateTime<Utc>>,
+    /// Null until revoked. Revoked kiosks can no longer swipe.
+    revoked_at: Option<chrono::DateTime<Utc>>,
+}
+
+#[derive(Object, Debug)]
+#[oai(rename = "KioskRegisterRequest")]
+pub(super) struct RegisterRequest {
+    /// Shown to admins to tell kiosks apart, e.g. "Shop door"
+    name: String,
+}
+
+#[derive(Object)]
+#[oai(rename = "KioskRegisterResponse")]
+pub(super) struct RegisterResponse {
+    kiosk: Kiosk,
+    /// Kiosk credential. Only ever returned here, store it on the kiosk.
+    token: String,
+}
+
+#[derive(Object)]
+#[oai(rename = "KioskQueryManyResponse")]
+pub(super) struct QueryManyResponse {
+    kiosks: Vec<Kiosk>,
+}
+
+#[derive(Object)]
+#[oai(rename = "KioskTOTPRequest")]
+pub(super) struct TotpRequest {
+    hour_type: HourType,
+}
+
+/// TOTP Generator Data, with the same parameters as `TOTPResponse`
+#[derive(Object)]
+#[oai(rename = "KioskTOTPResponse")]
+pub(super) struct TotpResponse {
+    /// Base32-encoded TOTP secret
+    secret: String,
+    /// The `issuer` to swipe as
+    issuer: String,
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError, HourTypeError)]
+pub(super) enum KioskError {
+    /// The kiosk token is invalid, or the kiosk was revoked
+    #[oai(status = 401)]
+    #[construct("Invalid kiosk token")]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    /// No unrevoked kiosk with the given ID exists
+    #[oai(status = 404)]
+    #[construct("Kiosk not found")]
+    NotFound(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    #[from(totp_rs::SecretParseError, "Failed to generate secret")]
+    InternalServerError(PlainText<String>),
+}
+
+fn hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+impl KioskToken {
+    /// Look up the unrevoked kiosk holding this token, returning its ID
+    pub(super) async fn verify(&self, pg: &PgPool) -> Result<String, KioskError> {
+        sqlx::query_scalar!(
+            r#"
+            UPDATE kiosks
+            SET last_seen_at = NOW()
+            WHERE token_hash = $1 AND revoked_at IS NULL
+            RETURNING id
+            "#,
+            hash(&self.0.token),
+        )
+        .fetch_optional(pg)
+        .await?
+        .ok_or(KioskError::unauthorized())
+    }
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn query_many(pg: PgPool) -> Result<QueryManyResponse, KioskError> {
+    let kiosks = sqlx::query_as!(
+        Kiosk,
+        r#"
+        SELECT id, name, registered_by, registered_at, last_seen_at, revoked_at
+        FROM kiosks
+        ORDER BY registered_at DESC
+        "#,
+    )
+    .fetch_all(&pg)
+    .await?;
+
+    Ok(QueryManyResponse { kiosks })
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn register(
+    RegisterRequest { name }: RegisterRequest,
+    admin_id: String,
+    pg: PgPool,
+) -> Result<RegisterResponse, KioskError> {
+    let mut token = [0u8; 32];
+    rng().fill_bytes(&mut token);
+    let token = hex::encode(token);
+
+    let kiosk = sqlx::query_as!(
+        Kiosk,
+        r#"
+        INSERT INTO kiosks (id, name, token_hash, registered_by)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, name, registered_by, registered_at, last_seen_at, revoked_at
+        "#,
+        cuid2(),
+        name,
+        hash(&token),
+        admin_id,
+    )
+    .fetch_one(&pg)
+    .await?;
+
+    Ok(RegisterResponse { kiosk, token })
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn revoke(id: String, pg: PgPool) -> Result<Kiosk, KioskError> {
+    sqlx::query_as!(
+        Kiosk,
+        r#"
+        UPDATE kiosks
+        SET revoked_at = NOW()
+        WHERE id = $1 AND revoked_at IS NULL
+        RETURNING id, name, registered_by, registered_at, last_seen_at, revoked_at
+        "#,
+        id,
+    )
+    .fetch_optional(&pg)
+    .await?
+    .ok_or(KioskError::not_found())
+}
+
+/// Get the kiosk's TOTP secret for `hour_type`, generating one if needed.
+/// Each hour type has its own secret, so one kiosk can show several codes.
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn totp(
+    TotpRequest { hour_type }: TotpRequest,
+    kiosk_id: String,
+    pg: PgPool,
+) -> Result<TotpResponse, KioskError> {
+    let secret = Secret::generate_secret().to_bytes()?;
+
+    // keep the existing secret, if any, so running kiosks aren't broken
+    let secret = sqlx::query_scalar!(
+        r#"
+        INSERT INTO kiosk_otps (kiosk_id, hour_type, secret)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (kiosk_id, hour_type) DO UPDATE
+        SET secret = kiosk_otps.secret
+        RETURNING secret
+        "#,
+        kiosk_id,
+        hour_type.as_str(),
+        secret,
+    )
+    .fetch_one(&pg)
+    .await?;
+
+    let Secret::Encoded(secret) = Secret::Raw(secret).to_encoded() else {
+        unreachable!()
+    };
+
+    Ok(TotpResponse {
+        secret,
+        issuer: kiosk_id,
+    })
+}
+
+/// If `issuer` is a kiosk, mark it as seen and return the admin it swipes on
+/// behalf of, which is None if they have since been deleted.
+///
+/// Returns None if `issuer` is not an unrevoked kiosk.
+pub(super) async fn sponsor(
+    issuer: &str,
+    pg: &PgPool,
+) -> Result<Option<Option<String>>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        UPDATE kiosks
+        SET last_seen_at = NOW()
+        WHERE id = $1 AND revoked_at IS NULL
+        RETURNING registered_by
+        "#,
+        issuer,
+    )
+    .fetch_optional(pg)
+    .await
+}
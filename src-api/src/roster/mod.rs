@@ -1,7 +1,18 @@
+pub(crate) mod auto_close;
+mod badge;
+pub(crate) mod bulk;
 mod crud;
+mod device;
+pub(crate) mod geofence;
+pub(crate) mod group;
 mod hour_type;
+mod kiosk;
+mod overlap;
 mod present;
+mod schedule;
+pub(crate) mod session;
 mod swipe;
+pub(crate) mod switch;
 mod totp;
 
 use futures_util::stream::BoxStream;
@@ -34,6 +45,228 @@ impl RosterService {
         Ok(Json(swipe::route(request.0, self.pg.clone()).await?))
     }
 
+    /// Replays swipes queued by a kiosk while it was offline.
+    #[oai(path = "/swipe/batch", method = "post")]
+    async fn swipe_batch(
+        &self,
+        request: Json<swipe::BatchRequest>,
+    ) -> Result<Json<swipe::BatchResponse>, swipe::SwipeError> {
+        Ok(Json(swipe::batch(request.0, self.pg.clone()).await?))
+    }
+
+    /// Swipes a student in or out by their badge instead of their ID.
+    #[oai(path = "/swipe/badge", method = "post")]
+    async fn swipe_badge(
+        &self,
+        request: Json<swipe::BadgeRequest>,
+    ) -> Result<Json<swipe::Response>, swipe::SwipeError> {
+        Ok(Json(swipe::badge_route(request.0, self.pg.clone()).await?))
+    }
+
+    /// Signs a student in or out from their own enrolled device.
+    #[oai(path = "/swipe/self", method = "post")]
+    async fn swipe_self(
+        &self,
+        request: Json<swipe::SelfRequest>,
+    ) -> Result<Json<swipe::Response>, swipe::SwipeError> {
+        Ok(Json(swipe::self_route(request.0, self.pg.clone()).await?))
+    }
+
+    #[oai(path = "/device", method = "get")]
+    async fn device_query_many(
+        &self,
+        /// Only include devices enrolled for this student
+        sid_hashed: Query<Option<String>>,
+        jwt: Jwt,
+    ) -> Result<Json<device::QueryManyResponse>, device::DeviceError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::StudentView)?;
+
+        Ok(Json(
+            device::query_many(sid_hashed.0, self.pg.clone()).await?,
+        ))
+    }
+
+    #[oai(path = "/device", method = "post")]
+    async fn device_enroll(
+        &self,
+        request: Json<device::EnrollRequest>,
+        jwt: Jwt,
+    ) -> Result<Json<device::EnrollResponse>, device::DeviceError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::StudentEdit)?;
+
+        Ok(Json(
+            device::enroll(request.0, claims.sub, self.pg.clone()).await?,
+        ))
+    }
+
+    #[oai(path = "/device/:id", method = "delete")]
+    async fn device_revoke(
+        &self,
+        id: Path<String>,
+        jwt: Jwt,
+    ) -> Result<Json<device::Device>, device::DeviceError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::StudentEdit)?;
+
+        Ok(Json(device::revoke(id.0, self.pg.clone()).await?))
+    }
+
+    #[oai(path = "/kiosk", method = "get")]
+    async fn kiosk_query_many(
+        &self,
+        jwt: Jwt,
+    ) -> Result<Json<kiosk::QueryManyResponse>, kiosk::KioskError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::Roster)?;
+
+        Ok(Json(kiosk::query_many(self.pg.clone()).await?))
+    }
+
+    /// Registers a kiosk that takes swipes on behalf of the calling admin,
+    /// without holding their JWT.
+    #[oai(path = "/kiosk", method = "post")]
+    async fn kiosk_register(
+        &self,
+        request: Json<kiosk::RegisterRequest>,
+        jwt: Jwt,
+    ) -> Result<Json<kiosk::RegisterResponse>, kiosk::KioskError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::Roster)?;
+
+        Ok(Json(
+            kiosk::register(request.0, claims.sub, self.pg.clone()).await?,
+        ))
+    }
+
+    #[oai(path = "/kiosk/:id", method = "delete")]
+    async fn kiosk_revoke(
+        &self,
+        id: Path<String>,
+        jwt: Jwt,
+    ) -> Result<Json<kiosk::Kiosk>, kiosk::KioskError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::Roster)?;
+
+        Ok(Json(kiosk::revoke(id.0, self.pg.clone()).await?))
+    }
+
+    /// Gets the calling kiosk's TOTP secret for an hour type.
+    #[oai(path = "/kiosk/totp", method = "post")]
+    async fn kiosk_totp(
+        &self,
+        request: Json<kiosk::TotpRequest>,
+        token: kiosk::KioskToken,
+    ) -> Result<Json<kiosk::TotpResponse>, kiosk::KioskError> {
+        let kiosk_id = token.verify(&self.pg).await?;
+
+        Ok(Json(
+            kiosk::totp(request.0, kiosk_id, self.pg.clone()).await?,
+        ))
+    }
+
+    /// Lists hour types the calling kiosk can swipe right now.
+    #[oai(path = "/kiosk/allowed", method = "get")]
+    async fn kiosk_allowed(
+        &self,
+        token: kiosk::KioskToken,
+    ) -> Result<Json<Vec<HourType>>, kiosk::KioskError> {
+        token.verify(&self.pg).await?;
+
+        Ok(Json(hour_type::allowed(&self.pg).await?))
+    }
+
+    /// Moves students from one hour type to another without a gap, e.g. when
+    /// a build meeting turns into a demo.
+    #[oai(path = "/switch", method = "post")]
+    async fn switch(
+        &self,
+        request: Json<switch::Request>,
+        jwt: Jwt,
+    ) -> Result<Json<switch::Response>, switch::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::Roster)?;
+
+        Ok(Json(
+            switch::route(request.0, claims.sub, self.pg.clone()).await?,
+        ))
+    }
+
+    /// Signs in a group of students at once, e.g. when a bus arrives.
+    #[oai(path = "/group/sign-in", method = "post")]
+    async fn group_sign_in(
+        &self,
+        request: Json<group::SignInRequest>,
+        jwt: Jwt,
+    ) -> Result<Json<group::SignInResponse>, group::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::Roster)?;
+
+        Ok(Json(
+            group::sign_in(request.0, claims.sub, self.pg.clone()).await?,
+        ))
+    }
+
+    /// Signs out everyone signed in to an hour type at once, e.g. at the end
+    /// of a meeting.
+    #[oai(path = "/group/sign-out", method = "post")]
+    async fn group_sign_out(
+        &self,
+        request: Json<group::SignOutRequest>,
+        jwt: Jwt,
+    ) -> Result<Json<group::SignOutResponse>, group::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::Roster)?;
+
+        Ok(Json(
+            group::sign_out(request.0, claims.sub, self.pg.clone()).await?,
+        ))
+    }
+
+    #[oai(path = "/badge", method = "get")]
+    async fn badge_query_many(
+        &self,
+        /// Only include badges enrolled to this student
+        sid_hashed: Query<Option<String>>,
+        jwt: Jwt,
+    ) -> Result<Json<badge::QueryManyResponse>, badge::BadgeError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::StudentView)?;
+
+        Ok(Json(
+            badge::query_many(sid_hashed.0, self.pg.clone()).await?,
+        ))
+    }
+
+    #[oai(path = "/badge", method = "post")]
+    async fn badge_enroll(
+        &self,
+        request: Json<badge::EnrollRequest>,
+        jwt: Jwt,
+    ) -> Result<Json<badge::Badge>, badge::BadgeError> {
+        let claims = jwt.verify()?;
+        claims
+            .perms
+            .assert_any([Permission::StudentEdit, Permission::Roster])?;
+
+        Ok(Json(
+            badge::enroll(request.0, claims.sub, self.pg.clone()).await?,
+        ))
+    }
+
+    #[oai(path = "/badge/:id", method = "delete")]
+    async fn badge_revoke(
+        &self,
+        id: Path<String>,
+        jwt: Jwt,
+    ) -> Result<Json<badge::Badge>, badge::BadgeError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::StudentEdit)?;
+
+        Ok(Json(badge::revoke(id.0, self.pg.clone()).await?))
+    }
+
     #[oai(path = "/totp", method = "post")]
     async fn totp(
         &self,
@@ -66,21 +299,9 @@ impl RosterService {
         let claims = jwt.verify()?;
         claims.perms.assert(Permission::HoursView)?;
 
-        let stream = dbstream::stream::<dbstream::Record>().await;
-        let pg = self.pg.clone();
-
-        Ok(EventStream::new(Box::pin(stream.filter_map(move |_| {
-            let pg = pg.clone();
-            async move {
-                match present::query(pg).await {
-                    Ok(res) => Some(res),
-                    Err(err) => {
-                        tracing::error!("Error in roster stream: {}", err);
-                        None
-                    }
-                }
-            }
-        }))))
+        Ok(EventStream::new(Box::pin(
+            present::subscribe(&self.pg).await?,
+        )))
     }
 
     #[oai(path = "/", method = "get")]
@@ -93,6 +314,19 @@ impl RosterService {
         Ok(Json(crud::query_many(self.pg.clone()).await?))
     }
 
+    /// Lists records that overlap another record of the same student and hour
+    /// type, or duplicate one, so they can be cleaned up.
+    #[oai(path = "/overlaps", method = "get")]
+    async fn overlap_query(
+        &self,
+        jwt: Jwt,
+    ) -> Result<Json<overlap::Response>, overlap::QueryError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursView)?;
+
+        Ok(Json(overlap::query(self.pg.clone()).await?))
+    }
+
     #[oai(path = "/:id", method = "get")]
     async fn record_query_one(
         &self,
@@ -159,11 +393,70 @@ impl RosterService {
         ))
     }
 
+    /// Edits, deletes, re-types or shifts many records at once. Either every
+    /// record is changed, or none are.
+    #[oai(path = "/bulk", method = "post")]
+    async fn record_bulk(
+        &self,
+        request: Json<bulk::Request>,
+        jwt: Jwt,
+    ) -> Result<Json<bulk::Response>, bulk::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+
+        Ok(Json(bulk::route(request.0, claims, self.pg.clone()).await?))
+    }
+
+    /// Lists hour types that can be swiped right now. Kiosks should grey out
+    /// the rest.
     #[oai(path = "/allowed", method = "get")]
     async fn allowed(&self, jwt: Jwt) -> Result<Json<Vec<HourType>>, hour_type::HourTypeError> {
         jwt.verify()?;
         Ok(Json(hour_type::allowed(&self.pg).await?))
     }
+
+    #[oai(path = "/blackout", method = "get")]
+    async fn blackout_query_many(
+        &self,
+        /// Only include blackouts on or after this date
+        after: Query<Option<chrono::NaiveDate>>,
+        jwt: Jwt,
+    ) -> Result<Json<schedule::BlackoutQueryManyResponse>, schedule::ScheduleError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursView)?;
+
+        Ok(Json(
+            schedule::blackout_query_many(after.0, self.pg.clone()).await?,
+        ))
+    }
+
+    #[oai(path = "/blackout", method = "post")]
+    async fn blackout_add(
+        &self,
+        request: Json<schedule::BlackoutCreateRequest>,
+        jwt: Jwt,
+    ) -> Result<Json<schedule::Blackout>, schedule::ScheduleError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+
+        Ok(Json(
+            schedule::blackout_add(request.0, claims.sub, self.pg.clone()).await?,
+        ))
+    }
+
+    #[oai(path = "/blackout/:id", method = "delete")]
+    async fn blackout_delete(
+        &self,
+        id: Path<String>,
+        jwt: Jwt,
+    ) -> Result<Json<schedule::Blackout>, schedule::ScheduleError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+
+        Ok(Json(
+            schedule::blackout_delete(id.0, self.pg.clone()).await?,
+        ))
+    }
 }
 
 pub(crate) struct HourTypeService {
@@ -179,13 +472,48 @@ impl HourTypeService {
 #[auto_operation_ids]
 #[OpenApi(tag = "Tag::HourType", prefix_path = "/hour-type")]
 impl HourTypeService {
+    /// Lists every hour type. Public, so students can see what their hours
+    /// are for.
+    #[oai(path = "/", method = "get")]
+    async fn query_many(
+        &self,
+    ) -> Result<Json<Vec<hour_type::HourTypeDetails>>, hour_type::HourTypeError> {
+        Ok(Json(hour_type::query_many(self.pg.clone()).await?))
+    }
+
+    #[oai(path = "/", method = "post")]
+    async fn add(
+        &self,
+        request: Json<hour_type::HourTypeDetails>,
+        jwt: Jwt,
+    ) -> Result<(), hour_type::HourTypeConfigError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+        HourType::create(request.0, self.pg.clone()).await?;
+
+        Ok(())
+    }
+
+    #[oai(path = "/:kind", method = "delete")]
+    async fn delete(
+        &self,
+        kind: Path<HourType>,
+        jwt: Jwt,
+    ) -> Result<(), hour_type::HourTypeConfigError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+        kind.0.delete(self.pg.clone()).await?;
+
+        Ok(())
+    }
+
     #[oai(path = "/:kind", method = "put")]
     async fn update(
         &self,
         kind: Path<HourType>,
         request: Json<hour_type::HourTypeInfo>,
         jwt: Jwt,
-    ) -> Result<(), hour_type::HourTypeError> {
+    ) -> Result<(), hour_type::HourTypeConfigError> {
         let claims = jwt.verify()?;
         claims.perms.assert(Permission::HoursEdit)?;
         kind.0.update(request.0, self.pg.clone()).await?;
@@ -198,15 +526,130 @@ impl HourTypeService {
         &self,
         kind: Path<HourType>,
         jwt: Jwt,
-    ) -> Result<Json<hour_type::HourTypeInfo>, hour_type::HourTypeError> {
+    ) -> Result<Json<hour_type::HourTypeInfo>, hour_type::HourTypeConfigError> {
         let claims = jwt.verify()?;
         claims.perms.assert(Permission::HoursView)?;
 
         Ok(Json(kind.0.query(self.pg.clone()).await?))
     }
 
+    #[oai(path = "/:kind/auto-close", method = "get")]
+    async fn auto_close_query(
+        &self,
+        kind: Path<HourType>,
+        jwt: Jwt,
+    ) -> Result<Json<auto_close::AutoCloseConfig>, auto_close::AutoCloseError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursView)?;
+
+        Ok(Json(kind.0.auto_close(self.pg.clone()).await?))
+    }
+
+    #[oai(path = "/:kind/auto-close", method = "put")]
+    async fn auto_close_update(
+        &self,
+        kind: Path<HourType>,
+        request: Json<auto_close::AutoCloseConfig>,
+        jwt: Jwt,
+    ) -> Result<(), auto_close::AutoCloseError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+        kind.0.update_auto_close(request.0, self.pg.clone()).await?;
+
+        Ok(())
+    }
+
+    #[oai(path = "/:kind/session-policy", method = "get")]
+    async fn session_policy_query(
+        &self,
+        kind: Path<HourType>,
+        jwt: Jwt,
+    ) -> Result<Json<session::SessionPolicy>, session::SessionPolicyError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursView)?;
+
+        Ok(Json(kind.0.session_policy(&self.pg).await?))
+    }
+
+    #[oai(path = "/:kind/session-policy", method = "put")]
+    async fn session_policy_update(
+        &self,
+        kind: Path<HourType>,
+        request: Json<session::SessionPolicy>,
+        jwt: Jwt,
+    ) -> Result<(), session::SessionPolicyError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+        kind.0
+            .update_session_policy(request.0, self.pg.clone())
+            .await?;
+
+        Ok(())
+    }
+
+    #[oai(path = "/:kind/geofence", method = "get")]
+    async fn geofence_query(
+        &self,
+        kind: Path<HourType>,
+        jwt: Jwt,
+    ) -> Result<Json<geofence::GeofenceConfig>, geofence::GeofenceError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursView)?;
+
+        Ok(Json(kind.0.geofence(&self.pg).await?))
+    }
+
+    #[oai(path = "/:kind/geofence", method = "put")]
+    async fn geofence_update(
+        &self,
+        kind: Path<HourType>,
+        request: Json<geofence::GeofenceConfig>,
+        jwt: Jwt,
+    ) -> Result<(), geofence::GeofenceError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+        kind.0.update_geofence(request.0, self.pg.clone()).await?;
+
+        Ok(())
+    }
+
+    #[oai(path = "/:kind/swipe-windows", method = "get")]
+    async fn swipe_windows_query(
+        &self,
+        kind: Path<HourType>,
+        jwt: Jwt,
+    ) -> Result<Json<Vec<schedule::SwipeWindow>>, schedule::ScheduleError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursView)?;
+
+        Ok(Json(kind.0.swipe_windows(&self.pg).await?))
+    }
+
+    /// Replaces every swipe window for the hour type. With no windows, the
+    /// hour type can be swiped at any time of day.
+    #[oai(path = "/:kind/swipe-windows", method = "put")]
+    async fn swipe_windows_update(
+        &self,
+        kind: Path<HourType>,
+        request: Json<Vec<schedule::SwipeWindow>>,
+        jwt: Jwt,
+    ) -> Result<(), schedule::ScheduleError> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+        kind.0
+            .update_swipe_windows(request.0, self.pg.clone())
+            .await?;
+
+        Ok(())
+    }
+
     #[oai(path = "/:kind/goal", method = "get")]
-    async fn goal(&self, kind: Path<HourType>) -> Result<Json<f64>, hour_type::HourTypeError> {
-        Ok(Json(kind.0.goal(self.pg.clone()).await?))
+    async fn goal(
+        &self,
+        kind: Path<HourType>,
+        /// Defaults to the current goal
+        season: Query<Option<String>>,
+    ) -> Result<Json<f64>, hour_type::HourTypeConfigError> {
+        Ok(Json(kind.0.goal(season.0, self.pg.clone()).await?))
     }
 }
@@ -0,0 +1,144 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{dbstream::Record, prelude::*};
+
+/// Records of one student and hour type that overlap each other, directly or
+/// through one another
+#[derive(Object)]
+pub(super) struct OverlapGroup {
+    hour_type: HourType,
+    /// Earliest sign-in first
+    records: Vec<Record>,
+}
+
+#[derive(Object)]
+#[oai(rename = "RosterOverlapResponse")]
+pub(super) struct Response {
+    /// Overlapping groups by student. Students without overlaps are omitted.
+    students: BTreeMap<String, Vec<OverlapGroup>>,
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum QueryError {
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+/// Whether a session of `sid_hashed` and `kind` from `sign_in` to `sign_out`
+/// overlaps any other record of theirs, ignoring the record `exclude`.
+///
+/// Sessions that haven't been signed out of are treated as lasting until now.
+/// Sessions that share a sign-in always overlap, even if empty.
+pub(super) async fn overlaps(
+    sid_hashed: &str,
+    kind: &HourType,
+    sign_in: chrono::DateTime<Utc>,
+    sign_out: Option<chrono::DateTime<Utc>>,
+    exclude: Option<&str>,
+    pg: impl sqlx::PgExecutor<'_>,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM records
+            WHERE sid_hashed = $1
+                AND hour_type = $2
+                AND ($3::text IS NULL OR id <> $3)
+                AND (
+                    sign_in = $4
+                    OR tstzrange(sign_in, GREATEST(sign_in, COALESCE(sign_out, NOW())))
+                        && tstzrange($4, GREATEST($4, COALESCE($5, NOW())))
+                )
+        ) AS "overlaps!"
+        "#,
+        sid_hashed,
+        kind.as_str(),
+        exclude,
+        sign_in,
+        sign_out,
+    )
+    .fetch_one(pg)
+    .await
+}
+
+/// Find the representative of `id`'s group
+fn root(parents: &mut HashMap<String, String>, id: &str) -> String {
+    let mut root = id.to_owned();
+    while let Some(parent) = parents.get(&root)
+        && *parent != root
+    {
+        root = parent.clone();
+    }
+
+    parents.insert(id.to_owned(), root.clone());
+    root
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn query(pg: PgPool) -> Result<Response, QueryError> {
+    let pairs = sqlx::query!(
+        r#"
+        SELECT a.id AS a, b.id AS b
+        FROM records a
+        JOIN records b
+            ON a.sid_hashed = b.sid_hashed
+            AND a.hour_type = b.hour_type
+            AND a.id < b.id
+        WHERE a.sign_in = b.sign_in
+            OR tstzrange(a.sign_in, GREATEST(a.sign_in, COALESCE(a.sign_out, NOW())))
+                && tstzrange(b.sign_in, GREATEST(b.sign_in, COALESCE(b.sign_out, NOW())))
+        "#
+    )
+    .fetch_all(&pg)
+    .await?;
+
+    let mut parents = HashMap::new();
+    for pair in pairs {
+        let (a, b) = (root(&mut parents, &pair.a), root(&mut parents, &pair.b));
+        parents.insert(a, b);
+    }
+
+    let ids = parents.keys().cloned().collect::<Vec<_>>();
+
+    let records = sqlx::query_as::<_, Record>(
+        r#"
+        SELECT *
+        FROM records
+        WHERE id = ANY($1)
+        ORDER BY sign_in
+        "#,
+    )
+    .bind(&ids)
+    .fetch_all(&pg)
+    .await?;
+
+    let mut groups = HashMap::<String, Vec<Record>>::new();
+    for record in records {
+        let group = root(&mut parents, &record.id);
+        groups.entry(group).or_default().push(record);
+    }
+
+    let mut students = BTreeMap::<String, Vec<OverlapGroup>>::new();
+    for records in groups.into_values() {
+        let (sid_hashed, hour_type) = (records[0].sid_hashed.clone(), records[0].hour_type.clone());
+
+        students
+            .entry(sid_hashed)
+            .or_default()
+            .push(OverlapGroup { hour_type, records });
+    }
+
+    for groups in students.values_mut() {
+        groups.sort_by_key(|group| group.records[0].sign_in);
+    }
+
+    Ok(Response { students })
+}
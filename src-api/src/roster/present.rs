@@ -1,12 +1,46 @@
-use std::collections::HashSet;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
-use crate::prelude::*;
+use poem_openapi::types::MaybeUndefined;
+use tokio::sync::{OnceCell, watch};
+use tokio_stream::wrappers::{BroadcastStream, WatchStream};
 
-#[derive(Object, Serialize)]
+use crate::{
+    dbstream::{Identifiable, Record, Replication, Student},
+    prelude::*,
+    roster::session::SessionPolicy,
+};
+
+/// How often the shared presence state is rebuilt from its open records, so
+/// elapsed times stay current and records that are no longer open (e.g. past
+/// midnight) drop out. Also reloads session policies and hour types.
+const REFRESH_INTERVAL: Duration = Duration::from_mins(1);
+
+/// An open record of a present student
+#[derive(Object, Serialize, Debug, Clone)]
+pub(super) struct PresentRecord {
+    record_id: String,
+    sid_hashed: String,
+    hour_type: HourType,
+    sign_in: chrono::DateTime<Utc>,
+    /// Time since `sign_in`, as of when the response was made
+    elapsed_seconds: i64,
+}
+
+#[derive(Object, Serialize, Clone)]
 #[oai(rename = "PresentResponse")]
 pub(super) struct Response {
     present: HashSet<String>,
     absent: HashSet<String>,
+    /// Every open record behind `present`, earliest sign-in first. Students
+    /// signed in under several hour types appear once per hour type.
+    records: Vec<PresentRecord>,
+    /// Present students by hour type ID. Every hour type is present, even if
+    /// zero.
+    counts: BTreeMap<String, u32>,
 }
 
 #[derive(ApiResponse, ApiError)]
@@ -23,37 +57,250 @@ pub(super) enum QueryError {
     InternalServerError(PlainText<String>),
 }
 
+struct OpenRecord {
+    sid_hashed: String,
+    hour_type: HourType,
+    sign_in: chrono::DateTime<Utc>,
+    multi_day: bool,
+}
+
+/// Everything presence is computed from
+#[derive(Default)]
+struct State {
+    /// Records without a sign-out, by ID. Some may no longer be open under
+    /// their session policy.
+    records: HashMap<String, OpenRecord>,
+    students: HashSet<String>,
+    policies: Arc<HashMap<HourType, SessionPolicy>>,
+    kinds: Vec<HourType>,
+}
+
+impl State {
+    async fn load(pg: &PgPool) -> Result<Self, sqlx::Error> {
+        let records = sqlx::query!(
+            r#"
+            SELECT id, sid_hashed, hour_type AS "hour_type: HourType", sign_in, multi_day
+            FROM records
+            WHERE sign_out IS NULL
+            "#
+        )
+        .fetch_all(pg)
+        .await?
+        .into_iter()
+        .map(|r| {
+            let record = OpenRecord {
+                sid_hashed: r.sid_hashed,
+                hour_type: r.hour_type,
+                sign_in: r.sign_in,
+                multi_day: r.multi_day,
+            };
+
+            (r.id, record)
+        })
+        .collect();
+
+        let students = sqlx::query_scalar!(r#"SELECT id_hashed FROM students"#)
+            .fetch_all(pg)
+            .await?
+            .into_iter()
+            .collect();
+
+        let mut state = Self {
+            records,
+            students,
+            ..Self::default()
+        };
+        state.refresh(pg).await?;
+
+        Ok(state)
+    }
+
+    /// Reload session policies and hour types
+    async fn refresh(&mut self, pg: &PgPool) -> Result<(), sqlx::Error> {
+        self.policies = SessionPolicy::all(pg).await?;
+        self.kinds = HourType::all(pg).await?;
+
+        Ok(())
+    }
+
+    /// Apply a replicated change to `records`. Returns false if the change
+    /// can't be applied and the state must be reloaded.
+    fn apply_record(&mut self, repl: Replication<Record>) -> bool {
+        match repl {
+            Replication::Insert(record) => {
+                if record.sign_out.is_none() {
+                    self.records.insert(
+                        record.id,
+                        OpenRecord {
+                            sid_hashed: record.sid_hashed,
+                            hour_type: record.hour_type,
+                            sign_in: record.sign_in,
+                            multi_day: record.multi_day,
+                        },
+                    );
+                }
+            }
+            Replication::Update(partial) => match partial.sign_out {
+                MaybeUndefined::Value(_) => {
+                    self.records.remove(&partial.id);
+                }
+                // reopened, and updates only carry changed columns
+                MaybeUndefined::Null => return false,
+                MaybeUndefined::Undefined => {
+                    if let Some(record) = self.records.get_mut(&partial.id) {
+                        if let Some(sid_hashed) = partial.sid_hashed {
+                            record.sid_hashed = sid_hashed;
+                        }
+                        if let Some(hour_type) = partial.hour_type {
+                            record.hour_type = hour_type;
+                        }
+                        if let Some(sign_in) = partial.sign_in {
+                            record.sign_in = sign_in;
+                        }
+                        if let Some(multi_day) = partial.multi_day {
+                            record.multi_day = multi_day;
+                        }
+                    }
+                }
+            },
+            Replication::Delete(deletion) => {
+                self.records.remove(deletion.pkey());
+            }
+        }
+
+        true
+    }
+
+    fn apply_student(&mut self, repl: Replication<Student>) {
+        match repl {
+            Replication::Insert(student) => {
+                self.students.insert(student.id_hashed);
+            }
+            Replication::Update(_) => {}
+            Replication::Delete(deletion) => {
+                self.students.remove(deletion.pkey());
+            }
+        }
+    }
+
+    fn snapshot(&self, now: chrono::DateTime<Utc>) -> Response {
+        let mut open = self
+            .records
+            .iter()
+            .filter(|(_, r)| {
+                let policy = self.policies.get(&r.hour_type).copied().unwrap_or_default();
+                policy.open_at(r.multi_day, r.sign_in, now)
+            })
+            .map(|(id, r)| PresentRecord {
+                record_id: id.clone(),
+                sid_hashed: r.sid_hashed.clone(),
+                hour_type: r.hour_type.clone(),
+                sign_in: r.sign_in,
+                elapsed_seconds: (now - r.sign_in).num_seconds(),
+            })
+            .collect::<Vec<_>>();
+        open.sort_by_key(|r| r.sign_in);
+
+        let mut counts = self
+            .kinds
+            .iter()
+            .map(|kind| (kind.to_string(), 0))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut present = HashSet::new();
+
+        for record in &open {
+            *counts.entry(record.hour_type.to_string()).or_default() += 1;
+            present.insert(record.sid_hashed.clone());
+        }
+
+        let absent = self
+            .students
+            .iter()
+            .filter(|sid_hashed| !present.contains(*sid_hashed))
+            .cloned()
+            .collect::<HashSet<_>>();
+
+        Response {
+            present,
+            absent,
+            records: open,
+            counts,
+        }
+    }
+}
+
 #[tracing::instrument(skip(pg), err)]
 pub(super) async fn query(pg: PgPool) -> Result<Response, QueryError> {
-    let records = sqlx::query!(
-        r#"
-        SELECT sid_hashed, sign_in FROM records
-        WHERE sign_out IS NULL
-        "#
-    )
-    .fetch_all(&pg)
-    .await?;
-
-    let students = sqlx::query!(r#"SELECT id_hashed FROM students"#)
-        .fetch_all(&pg)
-        .await?;
+    Ok(State::load(&pg).await?.snapshot(Utc::now()))
+}
+
+/// Keep `state` up to date with replicated changes, publishing a new snapshot
+/// to `tx` after each
+async fn maintain(
+    mut state: State,
+    mut records: BroadcastStream<Replication<Record>>,
+    mut students: BroadcastStream<Replication<Student>>,
+    tx: watch::Sender<Response>,
+    pg: PgPool,
+) {
+    let mut interval = tokio::time::interval(REFRESH_INTERVAL);
 
-    let open_today = records.into_iter().filter(|r| {
-        let sign_in_local = r.sign_in;
-        sign_in_local.date_naive() == Local::now().date_naive()
-    });
+    loop {
+        // a lagged stream has dropped changes, so start over
+        let reload = tokio::select! {
+            Some(repl) = records.next() => match repl {
+                Ok(repl) => !state.apply_record(repl),
+                Err(_) => true,
+            },
+            Some(repl) = students.next() => match repl {
+                Ok(repl) => {
+                    state.apply_student(repl);
+                    false
+                }
+                Err(_) => true,
+            },
+            _ = interval.tick() => {
+                state.refresh(&pg).await.log();
+                false
+            }
+        };
 
-    let mut present = HashSet::new();
+        if reload {
+            match State::load(&pg).await {
+                Ok(loaded) => state = loaded,
+                Err(err) => {
+                    error!(error = %err, "failed to reload presence state");
+                    continue;
+                }
+            }
+        }
 
-    for record in open_today {
-        present.insert(record.sid_hashed);
+        tx.send_replace(state.snapshot(Utc::now()));
     }
+}
 
-    let absent = students
-        .into_iter()
-        .map(|s| s.id_hashed)
-        .filter(|sid_hashed| !present.contains(sid_hashed))
-        .collect::<HashSet<_>>();
+/// Subscribe to presence, shared between every subscriber and maintained from
+/// replicated changes rather than queried per change.
+///
+/// Yields the current presence immediately, then again after every change.
+pub(super) async fn subscribe(pg: &PgPool) -> Result<WatchStream<Response>, QueryError> {
+    static PRESENCE: OnceCell<watch::Receiver<Response>> = OnceCell::const_new();
+
+    let rx = PRESENCE
+        .get_or_try_init(|| async {
+            // subscribe first, so nothing is missed while loading
+            let records = dbstream::stream::<Record>().await;
+            let students = dbstream::stream::<Student>().await;
+
+            let state = State::load(pg).await?;
+            let (tx, rx) = watch::channel(state.snapshot(Utc::now()));
+
+            tokio::spawn(maintain(state, records, students, tx, pg.clone()));
+
+            Ok::<_, sqlx::Error>(rx)
+        })
+        .await?;
 
-    Ok(Response { present, absent })
+    Ok(WatchStream::new(rx.clone()))
 }
@@ -0,0 +1,343 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{Datelike, NaiveDate, NaiveTime};
+
+use crate::{cache::Cached, prelude::*};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Enum)]
+#[oai(rename_all = "snake_case")]
+pub(super) enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+/// A time of day during which an hour type may be swiped, every week
+#[derive(Object, Debug, Clone, Copy)]
+pub(super) struct SwipeWindow {
+    pub weekday: Weekday,
+    /// In server's local time
+    pub starts: NaiveTime,
+    /// In server's local time, must be after `starts`
+    pub ends: NaiveTime,
+}
+
+/// A day on which swipes are blocked, e.g. a school holiday
+#[derive(Object, Debug, Clone)]
+pub(super) struct Blackout {
+    id: String,
+    date: NaiveDate,
+    /// If null, every hour type is blocked
+    hour_type: Option<HourType>,
+    reason: Option<String>,
+    created_by: Option<String>,
+    created_at: chrono::DateTime<Utc>,
+}
+
+#[derive(Object, Debug)]
+#[oai(rename = "BlackoutCreateRequest")]
+pub(super) struct BlackoutCreateRequest {
+    date: NaiveDate,
+    /// Omit to block every hour type
+    hour_type: Option<HourType>,
+    /// Shown to students whose swipes are blocked
+    reason: Option<String>,
+}
+
+#[derive(Object)]
+#[oai(rename = "BlackoutQueryManyResponse")]
+pub(super) struct BlackoutQueryManyResponse {
+    blackouts: Vec<Blackout>,
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum ScheduleError {
+    #[oai(status = 400)]
+    #[construct(invalid, "Swipe windows must end after they start")]
+    BadRequest(PlainText<String>),
+
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    /// No blackout with the given ID exists
+    #[oai(status = 404)]
+    #[construct("Blackout not found")]
+    NotFound(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+/// Why an hour type can't be swiped at some time
+pub(super) enum Closed {
+    /// The day is blacked out, possibly with a reason
+    Blackout(Option<String>),
+    /// The time is outside every swipe window
+    OutsideHours,
+}
+
+impl Weekday {
+    fn number_from_monday(self) -> i16 {
+        self as i16 + 1
+    }
+
+    fn from_number_from_monday(n: i16) -> Self {
+        match n {
+            1 => Self::Monday,
+            2 => Self::Tuesday,
+            3 => Self::Wednesday,
+            4 => Self::Thursday,
+            5 => Self::Friday,
+            6 => Self::Saturday,
+            _ => Self::Sunday,
+        }
+    }
+}
+
+impl From<chrono::Weekday> for Weekday {
+    fn from(day: chrono::Weekday) -> Self {
+        Self::from_number_from_monday(day.number_from_monday() as i16)
+    }
+}
+
+/// A blackout, as needed to check swipes against it
+struct BlackoutDay {
+    date: NaiveDate,
+    hour_type: Option<HourType>,
+    reason: Option<String>,
+}
+
+/// Blackouts from yesterday on, which covers every swipe that can still be
+/// made, including queued ones
+async fn blackouts(pg: &PgPool) -> Result<Arc<Vec<BlackoutDay>>, sqlx::Error> {
+    static CACHE: Cached<Vec<BlackoutDay>> = Cached::new();
+
+    CACHE
+        .get(|| async {
+            sqlx::query_as!(
+                BlackoutDay,
+                r#"
+                SELECT date, hour_type AS "hour_type: HourType", reason
+                FROM blackouts
+                WHERE date >= $1
+                "#,
+                Local::now().date_naive() - chrono::Duration::days(1),
+            )
+            .fetch_all(pg)
+            .await
+        })
+        .await
+}
+
+/// Every hour type's swipe windows, in order of weekday and start time
+async fn all_swipe_windows(
+    pg: &PgPool,
+) -> Result<Arc<HashMap<HourType, Vec<SwipeWindow>>>, sqlx::Error> {
+    static CACHE: Cached<HashMap<HourType, Vec<SwipeWindow>>> = Cached::new();
+
+    CACHE
+        .get(|| async {
+            let rows = sqlx::query!(
+                r#"
+                SELECT hour_type AS "hour_type: HourType", weekday, starts, ends
+                FROM swipe_windows
+                ORDER BY weekday, starts
+                "#,
+            )
+            .fetch_all(pg)
+            .await?;
+
+            let mut windows = HashMap::<HourType, Vec<SwipeWindow>>::new();
+
+            for w in rows {
+                windows.entry(w.hour_type).or_default().push(SwipeWindow {
+                    weekday: Weekday::from_number_from_monday(w.weekday),
+                    starts: w.starts,
+                    ends: w.ends,
+                });
+            }
+
+            Ok(windows)
+        })
+        .await
+}
+
+impl HourType {
+    pub(super) async fn swipe_windows(&self, pg: &PgPool) -> Result<Vec<SwipeWindow>, sqlx::Error> {
+        Ok(all_swipe_windows(pg)
+            .await?
+            .get(self)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    /// Replace every swipe window for this hour type
+    pub(super) async fn update_swipe_windows(
+        &self,
+        windows: Vec<SwipeWindow>,
+        pg: PgPool,
+    ) -> Result<(), ScheduleError> {
+        if windows.iter().any(|w| w.ends <= w.starts) {
+            return Err(ScheduleError::invalid());
+        }
+
+        let mut tx = pg.begin().await?;
+
+        sqlx::query!(
+            r#"DELETE FROM swipe_windows WHERE hour_type = $1"#,
+            self.as_str(),
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for SwipeWindow {
+            weekday,
+            starts,
+            ends,
+        } in windows
+        {
+            sqlx::query!(
+                r#"
+                INSERT INTO swipe_windows (id, hour_type, weekday, starts, ends)
+                VALUES ($1, $2, $3, $4, $5)
+                "#,
+                cuid2(),
+                self.as_str(),
+                weekday.number_from_monday(),
+                starts,
+                ends,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Why this hour type can't be swiped at `at`, if it can't
+    pub(super) async fn closed(
+        &self,
+        at: chrono::DateTime<Utc>,
+        pg: &PgPool,
+    ) -> Result<Option<Closed>, sqlx::Error> {
+        let at = at.and_local();
+        let date = at.date_naive();
+
+        // prefer a blackout with a reason to show
+        let blackout = blackouts(pg)
+            .await?
+            .iter()
+            .filter(|b| b.date == date && b.hour_type.as_ref().is_none_or(|h| h == self))
+            .max_by_key(|b| b.reason.is_some())
+            .map(|b| b.reason.clone());
+
+        if let Some(reason) = blackout {
+            return Ok(Some(Closed::Blackout(reason)));
+        }
+
+        let windows = self.swipe_windows(pg).await?;
+        let (weekday, time) = (Weekday::from(at.weekday()), at.time());
+
+        let open = windows.is_empty()
+            || windows
+                .iter()
+                .any(|w| w.weekday == weekday && w.starts <= time && time <= w.ends);
+
+        Ok((!open).then_some(Closed::OutsideHours))
+    }
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn blackout_query_many(
+    after: Option<NaiveDate>,
+    pg: PgPool,
+) -> Result<BlackoutQueryManyResponse, ScheduleError> {
+    let blackouts = sqlx::query_as!(
+        Blackout,
+        r#"
+        SELECT
+            id,
+            date,
+            hour_type AS "hour_type: HourType",
+            reason,
+            created_by,
+            created_at
+        FROM blackouts
+        WHERE ($1::date IS NULL OR date >= $1)
+        ORDER BY date
+        "#,
+        after,
+    )
+    .fetch_all(&pg)
+    .await?;
+
+    Ok(BlackoutQueryManyResponse { blackouts })
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn blackout_add(
+    BlackoutCreateRequest {
+        date,
+        hour_type,
+        reason,
+    }: BlackoutCreateRequest,
+    admin_id: String,
+    pg: PgPool,
+) -> Result<Blackout, ScheduleError> {
+    let blackout = sqlx::query_as!(
+        Blackout,
+        r#"
+        INSERT INTO blackouts (id, date, hour_type, reason, created_by)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING
+            id,
+            date,
+            hour_type AS "hour_type: HourType",
+            reason,
+            created_by,
+            created_at
+        "#,
+        cuid2(),
+        date,
+        hour_type.as_ref().map(HourType::as_str),
+        reason,
+        admin_id,
+    )
+    .fetch_one(&pg)
+    .await?;
+
+    Ok(blackout)
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn blackout_delete(id: String, pg: PgPool) -> Result<Blackout, ScheduleError> {
+    sqlx::query_as!(
+        Blackout,
+        r#"
+        DELETE FROM blackouts
+        WHERE id = $1
+        RETURNING
+            id,
+            date,
+            hour_type AS "hour_type: HourType",
+            reason,
+            created_by,
+            created_at
+        "#,
+        id,
+    )
+    .fetch_optional(&pg)
+    .await?
+    .ok_or(ScheduleError::not_found())
+}
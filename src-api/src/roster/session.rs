@@ -0,0 +1,270 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{cache::Cached, prelude::*};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Enum, sqlx::Type)]
+#[oai(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "session_rounding", rename_all = "snake_case")]
+pub(crate) enum SessionRounding {
+    /// Round to the nearest increment
+    Nearest,
+    /// Round down to the previous increment
+    Floor,
+    /// Round up to the next increment
+    Ceil,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Enum, sqlx::Type)]
+#[oai(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "overlap_policy", rename_all = "snake_case")]
+pub(crate) enum OverlapPolicy {
+    /// Refuse to save the record
+    Reject,
+    /// Save the record, but flag it for review
+    Flag,
+    /// Don't check for overlaps
+    Allow,
+}
+
+#[derive(Object, Debug, Clone, Copy)]
+pub(crate) struct SessionPolicy {
+    /// Sessions shorter than this are credited zero hours, and cannot be
+    /// entered manually
+    pub min_minutes: i32,
+    /// Sessions longer than this are credited as if they were exactly this
+    /// long. If null, there is no maximum.
+    pub max_minutes: Option<i32>,
+    pub rounding: SessionRounding,
+    /// Granularity of `rounding`, e.g. 5 or 15 minutes
+    pub increment_minutes: i32,
+    /// A swipe within this many minutes of signing in will not sign the
+    /// student out, unless forced
+    pub debounce_minutes: i32,
+    /// Allow every session of this hour type to span midnight. Individual
+    /// records can also opt in.
+    pub multi_day: bool,
+    /// Longest a multi-day session may last, in hours
+    pub max_span_hours: i32,
+    /// What to do when a record entered or edited manually overlaps another
+    /// record of the same student and hour type
+    pub overlap: OverlapPolicy,
+}
+
+impl Default for SessionPolicy {
+    fn default() -> Self {
+        Self {
+            min_minutes: 0,
+            max_minutes: None,
+            rounding: SessionRounding::Floor,
+            increment_minutes: 1,
+            debounce_minutes: 3,
+            multi_day: false,
+            max_span_hours: 72,
+            overlap: OverlapPolicy::Reject,
+        }
+    }
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum SessionPolicyError {
+    #[oai(status = 400)]
+    #[construct(
+        invalid,
+        "Minutes must be nonnegative, increment and span must be positive, and max must be at \
+         least min"
+    )]
+    BadRequest(PlainText<String>),
+
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+impl SessionPolicy {
+    /// Whether a sign-out `elapsed` after sign-in should be debounced
+    pub(crate) fn debounced(&self, elapsed: chrono::Duration) -> bool {
+        elapsed.num_minutes() < i64::from(self.debounce_minutes)
+    }
+
+    /// Whether a session of length `duration` is too short to be entered
+    pub(crate) fn too_short(&self, duration: chrono::Duration) -> bool {
+        duration.num_minutes() < i64::from(self.min_minutes)
+    }
+
+    /// Whether a session may span midnight, given the record's own opt-in
+    pub(crate) fn spans_days(&self, multi_day: bool) -> bool {
+        self.multi_day || multi_day
+    }
+
+    /// Longest a multi-day session may last
+    pub(crate) fn max_span(&self) -> chrono::Duration {
+        chrono::Duration::hours(i64::from(self.max_span_hours))
+    }
+
+    /// Whether a record signed in at `sign_in` and not yet signed out can
+    /// still be signed out of at `at`
+    pub(crate) fn open_at(
+        &self,
+        multi_day: bool,
+        sign_in: chrono::DateTime<Utc>,
+        at: chrono::DateTime<Utc>,
+    ) -> bool {
+        if self.spans_days(multi_day) {
+            at >= sign_in && at - sign_in <= self.max_span()
+        } else {
+            sign_in.and_local().date_naive() == at.and_local().date_naive()
+        }
+    }
+
+    /// Number of hours credited for a session of length `duration`
+    pub(crate) fn credit(&self, duration: chrono::Duration) -> f64 {
+        if self.too_short(duration) {
+            return 0.0;
+        }
+
+        let minutes = duration.num_seconds() as f64 / 60.0;
+        let minutes = match self.max_minutes {
+            Some(max) => minutes.min(f64::from(max)),
+            None => minutes,
+        };
+
+        let increment = f64::from(self.increment_minutes.max(1));
+        let steps = minutes / increment;
+        let steps = match self.rounding {
+            SessionRounding::Nearest => steps.round(),
+            SessionRounding::Floor => steps.floor(),
+            SessionRounding::Ceil => steps.ceil(),
+        };
+
+        steps * increment / 60.0
+    }
+
+    /// Policies for every hour type, falling back to the default for any that
+    /// are not configured
+    pub(crate) async fn all(pg: &PgPool) -> Result<Arc<HashMap<HourType, Self>>, sqlx::Error> {
+        static CACHE: Cached<HashMap<HourType, SessionPolicy>> = Cached::new();
+
+        CACHE
+            .get(|| async {
+                let rows = sqlx::query!(
+                    r#"
+                    SELECT
+                        kind AS "kind: HourType",
+                        min_minutes,
+                        max_minutes,
+                        rounding AS "rounding: SessionRounding",
+                        increment_minutes,
+                        debounce_minutes,
+                        multi_day,
+                        max_span_hours,
+                        overlap AS "overlap: OverlapPolicy"
+                    FROM session_policy
+                    "#
+                )
+                .fetch_all(pg)
+                .await?;
+
+                Ok(rows
+                    .into_iter()
+                    .map(|row| {
+                        (
+                            row.kind,
+                            Self {
+                                min_minutes: row.min_minutes,
+                                max_minutes: row.max_minutes,
+                                rounding: row.rounding,
+                                increment_minutes: row.increment_minutes,
+                                debounce_minutes: row.debounce_minutes,
+                                multi_day: row.multi_day,
+                                max_span_hours: row.max_span_hours,
+                                overlap: row.overlap,
+                            },
+                        )
+                    })
+                    .collect())
+            })
+            .await
+    }
+}
+
+impl HourType {
+    pub(crate) async fn session_policy(&self, pg: &PgPool) -> Result<SessionPolicy, sqlx::Error> {
+        Ok(SessionPolicy::all(pg)
+            .await?
+            .get(self)
+            .copied()
+            .unwrap_or_default())
+    }
+
+    pub(super) async fn update_session_policy(
+        &self,
+        SessionPolicy {
+            min_minutes,
+            max_minutes,
+            rounding,
+            increment_minutes,
+            debounce_minutes,
+            multi_day,
+            max_span_hours,
+            overlap,
+        }: SessionPolicy,
+        pg: PgPool,
+    ) -> Result<(), SessionPolicyError> {
+        if min_minutes < 0
+            || debounce_minutes < 0
+            || increment_minutes <= 0
+            || max_span_hours <= 0
+            || max_minutes.is_some_and(|max| max < min_minutes)
+        {
+            return Err(SessionPolicyError::invalid());
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO session_policy (
+                kind,
+                min_minutes,
+                max_minutes,
+                rounding,
+                increment_minutes,
+                debounce_minutes,
+                multi_day,
+                max_span_hours,
+                overlap
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (kind) DO UPDATE
+            SET min_minutes = EXCLUDED.min_minutes,
+                max_minutes = EXCLUDED.max_minutes,
+                rounding = EXCLUDED.rounding,
+                increment_minutes = EXCLUDED.increment_minutes,
+                debounce_minutes = EXCLUDED.debounce_minutes,
+                multi_day = EXCLUDED.multi_day,
+                max_span_hours = EXCLUDED.max_span_hours,
+                overlap = EXCLUDED.overlap
+            "#,
+            self.as_str(),
+            min_minutes,
+            max_minutes,
+            rounding as SessionRounding,
+            increment_minutes,
+            debounce_minutes,
+            multi_day,
+            max_span_hours,
+            overlap as OverlapPolicy,
+        )
+        .execute(&pg)
+        .await?;
+
+        Ok(())
+    }
+}
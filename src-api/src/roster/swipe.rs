@@ -1,14 +1,25 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::HashMap};
 
 use poem_openapi::types::ToJSON;
 use totp_rs::{Algorithm, TOTP};
 
-use crate::{prelude::*, roster::hour_type::HourTypeError};
+use crate::{
+    prelude::*,
+    roster::{
+        badge, device,
+        geofence::{GeofencePolicy, Location},
+        hour_type::HourTypeError,
+        kiosk,
+        schedule::Closed,
+    },
+    season,
+    student::hours,
+};
 
 #[derive(Object)]
 #[oai(rename = "SwipeRequest")]
 pub(super) struct Request {
-    /// AKA: admin's ID, TOTP `account_name`
+    /// AKA: admin's or kiosk's ID, TOTP `account_name`
     issuer: String,
     totp: String,
     sid_hashed: String,
@@ -17,6 +28,106 @@ pub(super) struct Request {
     force: bool,
     #[oai(default)]
     action: Option<SwipeAction>,
+    /// Checked against the hour type's geofences, if any
+    location: Option<Location>,
+    /// Respond with a `SwipeSummary` instead of just the action, for kiosks
+    /// that show the student's progress
+    #[oai(default)]
+    summary: bool,
+}
+
+#[derive(Object)]
+#[oai(rename = "BadgeSwipeRequest")]
+pub(super) struct BadgeRequest {
+    /// AKA: admin's or kiosk's ID, TOTP `account_name`
+    issuer: String,
+    totp: String,
+    /// Hashed UID read from the badge, in place of `sid_hashed`
+    uid_hashed: String,
+    kind: HourType,
+    #[oai(default)]
+    force: bool,
+    #[oai(default)]
+    action: Option<SwipeAction>,
+    /// Checked against the hour type's geofences, if any
+    location: Option<Location>,
+    /// Respond with a `SwipeSummary` instead of just the action
+    #[oai(default)]
+    summary: bool,
+}
+
+#[derive(Object)]
+#[oai(rename = "SelfSwipeRequest")]
+pub(super) struct SelfRequest {
+    /// The admin or kiosk whose rotating code is on the display
+    issuer: String,
+    /// The rotating code on the display
+    totp: String,
+    /// Device credential, from enrollment
+    token: String,
+    kind: HourType,
+    #[oai(default)]
+    action: Option<SwipeAction>,
+    /// Checked against the hour type's geofences, if any
+    location: Option<Location>,
+    /// Respond with a `SwipeSummary` instead of just the action
+    #[oai(default)]
+    summary: bool,
+}
+
+/// How far in the past a queued swipe may still be replayed
+const MAX_QUEUE_AGE: chrono::Duration = chrono::Duration::days(1);
+
+/// How far in the future a queued swipe may be, to allow for clock drift
+const MAX_CLOCK_DRIFT: chrono::Duration = chrono::Duration::minutes(1);
+
+#[derive(Object, Debug)]
+#[oai(rename = "SwipeBatchItem")]
+pub(super) struct BatchItem {
+    /// Chosen by the kiosk, echoed back in the matching result
+    id: String,
+    /// When the swipe was made on the kiosk
+    timestamp: chrono::DateTime<Utc>,
+    /// The TOTP that was valid at `timestamp`
+    totp: String,
+    sid_hashed: String,
+    kind: HourType,
+    #[oai(default)]
+    force: bool,
+    #[oai(default)]
+    action: Option<SwipeAction>,
+    /// Where the swipe was made, checked against the hour type's geofences
+    location: Option<Location>,
+}
+
+#[derive(Object)]
+#[oai(rename = "SwipeBatchRequest")]
+pub(super) struct BatchRequest {
+    /// AKA: admin's or kiosk's ID, TOTP `account_name`
+    issuer: String,
+    /// Replayed in order of `timestamp`
+    swipes: Vec<BatchItem>,
+}
+
+#[derive(Object)]
+#[oai(rename = "SwipeBatchResult")]
+pub(super) struct BatchResult {
+    id: String,
+    /// Whether the swipe can be dropped from the kiosk's queue. Only false for
+    /// server errors, which are worth retrying.
+    acknowledged: bool,
+    /// Set if the swipe signed the student in or out
+    action: Option<SwipeAction>,
+    /// Set if the swipe was denied or ignored
+    fallthrough: Option<SwipeFallthrough>,
+    /// Set if the swipe was rejected
+    error: Option<String>,
+}
+
+#[derive(Object)]
+#[oai(rename = "SwipeBatchResponse")]
+pub(super) struct BatchResponse {
+    results: Vec<BatchResult>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Enum)]
@@ -33,9 +144,30 @@ pub(super) enum SwipeFallthrough {
     Ignored,
 }
 
+/// A sign-in or sign-out along with the student's progress in the hour type,
+/// credited the same way as `/student/{id_hashed}/hours`
+#[derive(Object, Debug)]
+#[oai(rename = "SwipeSummary")]
+pub(super) struct Summary {
+    action: SwipeAction,
+    /// Length of the session just signed out of. Null on sign-in.
+    session_seconds: Option<i64>,
+    /// Hours credited for the session just signed out of. Null on sign-in.
+    session_hours: Option<f64>,
+    /// Hours credited for sessions signed in to today, in server's local time
+    today_hours: f64,
+    /// Hours credited this season, including adjustments. Null if there is no
+    /// season yet.
+    season_hours: Option<f64>,
+    /// Goal for this season, or the current goal if there is no season yet
+    goal: f64,
+}
+
 pub(super) enum Response {
     Acted(SwipeAction),
     Fallthrough(SwipeFallthrough),
+    /// Only if the swipe asked for a summary
+    Summary(Summary),
 }
 
 impl poem_openapi::types::Type for Response {
@@ -56,9 +188,13 @@ impl poem_openapi::types::Type for Response {
         registry.create_schema::<Self, _>(Self::name().into_owned(), |registry| {
             SwipeAction::register(registry);
             SwipeFallthrough::register(registry);
+            Summary::register(registry);
             poem_openapi::registry::MetaSchema {
-                ty: "string",
-                any_of: vec![SwipeAction::schema_ref(), SwipeFallthrough::schema_ref()],
+                any_of: vec![
+                    SwipeAction::schema_ref(),
+                    SwipeFallthrough::schema_ref(),
+                    Summary::schema_ref(),
+                ],
                 ..poem_openapi::registry::MetaSchema::ANY
             }
         });
@@ -80,6 +216,7 @@ impl ToJSON for Response {
         match self {
             Response::Acted(action) => action.to_json(),
             Response::Fallthrough(fallthrough) => fallthrough.to_json(),
+            Response::Summary(summary) => summary.to_json(),
         }
     }
 }
@@ -87,9 +224,11 @@ impl ToJSON for Response {
 #[derive(ApiResponse, ApiError)]
 #[from(PermissionDeniedError, HourTypeError)]
 pub(super) enum SwipeError {
-    /// The given hour type is not allowed at this time. See `/roster/allowed`.
+    /// The given hour type is not allowed at this time (see `/roster/allowed`),
+    /// or a queued swipe's timestamp is out of range
     #[oai(status = 400)]
     #[construct(hour_type(HourType), "{source} hours are not allowed right now")]
+    #[construct(timestamp, "Swipe is too far in the past or in the future")]
     BadRequest(PlainText<String>),
 
     /// The provided TOTP is invalid
@@ -98,7 +237,10 @@ pub(super) enum SwipeError {
     #[construct("Invalid TOTP")]
     Unauthorized(PlainText<String>),
 
+    /// The swipe came from outside every geofence for the hour type, which
+    /// rejects such swipes
     #[oai(status = 403)]
+    #[construct(location, "Swipe is outside the allowed area")]
     Forbidden(PlainText<String>),
 
     /// No student with the given ID exists
@@ -106,13 +248,149 @@ pub(super) enum SwipeError {
     #[construct("Student not found")]
     NotFound(PlainText<String>),
 
+    /// The hour type can't be swiped right now, because the day is blacked out
+    /// or it's outside the hour type's swipe windows
+    #[oai(status = 423)]
+    #[construct(blackout(String), "Swipes are blocked today: {source}")]
+    #[construct(hours(HourType), "{source} swipes are not allowed at this time")]
+    Closed(PlainText<String>),
+
+    /// No student has the given badge enrolled. The kiosk should offer to
+    /// enroll it (see `/roster/badge`).
+    #[oai(status = 422)]
+    #[construct("Badge not enrolled")]
+    UnknownBadge(PlainText<String>),
+
     #[oai(status = 500)]
     #[from(sqlx::Error, "Database error")]
     InternalServerError(PlainText<String>),
 }
 
+/// Look up the TOTP secret `issuer` uses for `kind`, whether they are an admin
+/// or a kiosk
+async fn secret(issuer: &str, kind: &HourType, pg: &PgPool) -> Result<Vec<u8>, SwipeError> {
+    sqlx::query!(
+        r#"
+        SELECT secret AS "secret!" FROM otps
+        WHERE admin_id = $1 AND hour_type = $2
+        UNION ALL
+        SELECT o.secret FROM kiosk_otps o
+        JOIN kiosks k ON k.id = o.kiosk_id
+        WHERE o.kiosk_id = $1 AND o.hour_type = $2 AND k.revoked_at IS NULL
+        LIMIT 1
+        "#,
+        issuer,
+        kind.as_str()
+    )
+    .fetch_optional(pg)
+    .await?
+    .map(|r| r.secret)
+    .ok_or(SwipeError::unauthorized())
+}
+
+/// Verify `totp` against `secret`, either right now or at the time step
+/// containing `at`
+fn verify(
+    secret: Vec<u8>,
+    totp: &str,
+    at: Option<chrono::DateTime<Utc>>,
+) -> Result<(), SwipeError> {
+    let verifier = TOTP::new(Algorithm::SHA1, 6, 1, 30, secret)?;
+
+    let valid = match at {
+        Some(at) => u64::try_from(at.timestamp()).is_ok_and(|t| verifier.check(totp, t)),
+        None => verifier.check_current(totp).unwrap_or(false),
+    };
+
+    if !valid {
+        return Err(SwipeError::unauthorized());
+    }
+
+    Ok(())
+}
+
+/// Check `location` against the geofences for `kind`, returning whether the
+/// swipe should be flagged
+async fn locate(
+    kind: &HourType,
+    location: Option<&Location>,
+    pg: &PgPool,
+) -> Result<bool, SwipeError> {
+    let config = kind.geofence(pg).await?;
+
+    if !config.outside(location) {
+        return Ok(false);
+    }
+
+    match config.policy {
+        GeofencePolicy::Reject => Err(SwipeError::location()),
+        GeofencePolicy::Flag | GeofencePolicy::Ignore => Ok(true),
+    }
+}
+
+/// Check that `kind` can be signed in to at `at`, going by blackouts and swipe
+/// windows
+async fn open(kind: &HourType, at: chrono::DateTime<Utc>, pg: &PgPool) -> Result<(), SwipeError> {
+    match kind.closed(at, pg).await? {
+        Some(Closed::Blackout(reason)) => Err(SwipeError::blackout(
+            reason.unwrap_or_else(|| "blackout date".to_string()),
+        )),
+        Some(Closed::OutsideHours) => Err(SwipeError::hours(kind.clone())),
+        None => Ok(()),
+    }
+}
+
+/// Who a swipe was taken by
+struct Issuer {
+    /// The admin the swipe was made on behalf of
+    admin_id: String,
+    /// Set if the swipe was taken by a registered kiosk
+    kiosk_id: Option<String>,
+}
+
+/// Resolve `issuer` to the admin it takes swipes on behalf of, checking that
+/// they may take swipes at all
+async fn resolve(issuer: String, pg: &PgPool) -> Result<Issuer, SwipeError> {
+    let (admin_id, kiosk_id) = match kiosk::sponsor(&issuer, pg).await? {
+        Some(Some(admin_id)) => (admin_id, Some(issuer)),
+        // the admin who registered the kiosk was deleted
+        Some(None) => return Err(SwipeError::unauthorized()),
+        None => (issuer, None),
+    };
+
+    let claims = match jwt::Claims::new(admin_id, jwt::Claims::EXPIRY, pg).await {
+        Ok(claims) => claims,
+        Err(sqlx::Error::RowNotFound) => return Err(SwipeError::unauthorized()),
+        Err(err) => return Err(err.into()),
+    };
+    claims.perms.assert(Permission::Roster)?;
+
+    Ok(Issuer {
+        admin_id: claims.sub,
+        kiosk_id,
+    })
+}
+
+/// Check that `issuer` may take swipes for `kind` right now, returning who
+/// they are and whether the swipe should be flagged
+async fn authorize(
+    issuer: String,
+    kind: &HourType,
+    location: Option<&Location>,
+    pg: &PgPool,
+) -> Result<(Issuer, bool), SwipeError> {
+    let issuer = resolve(issuer, pg).await?;
+
+    if !kind.allowed(pg).await? {
+        return Err(SwipeError::hour_type(kind.clone()));
+    }
+
+    let flagged = locate(kind, location, pg).await?;
+
+    Ok((issuer, flagged))
+}
+
 #[tracing::instrument(skip(pg), err)]
-#[allow(clippy::too_many_lines)]
 pub(super) async fn route(
     Request {
         issuer,
@@ -121,82 +399,452 @@ pub(super) async fn route(
         kind,
         force,
         action,
+        location,
+        summary,
     }: Request,
     pg: PgPool,
 ) -> Result<Response, SwipeError> {
-    let Some(secret) = sqlx::query!(
-        r#"
-        SELECT secret FROM otps
-        WHERE admin_id = $1 AND hour_type = $2
-        "#,
+    verify(secret(&issuer, &kind, &pg).await?, &totp, None)?;
+
+    let (Issuer { admin_id, kiosk_id }, flagged) =
+        authorize(issuer, &kind, location.as_ref(), &pg).await?;
+
+    act(
+        Swipe {
+            sid_hashed,
+            kind,
+            force,
+            action,
+            at: Utc::now(),
+            location,
+            flagged,
+            device_id: None,
+            kiosk_id,
+            summary,
+        },
+        admin_id,
+        pg,
+    )
+    .await
+}
+
+/// A kiosk swipe identified by a badge instead of a student ID
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn badge_route(
+    BadgeRequest {
         issuer,
-        kind as HourType
+        totp,
+        uid_hashed,
+        kind,
+        force,
+        action,
+        location,
+        summary,
+    }: BadgeRequest,
+    pg: PgPool,
+) -> Result<Response, SwipeError> {
+    verify(secret(&issuer, &kind, &pg).await?, &totp, None)?;
+
+    let (Issuer { admin_id, kiosk_id }, flagged) =
+        authorize(issuer, &kind, location.as_ref(), &pg).await?;
+
+    let sid_hashed = badge::student(&uid_hashed, &pg)
+        .await?
+        .ok_or(SwipeError::unknown_badge())?;
+
+    act(
+        Swipe {
+            sid_hashed,
+            kind,
+            force,
+            action,
+            at: Utc::now(),
+            location,
+            flagged,
+            device_id: None,
+            kiosk_id,
+            summary,
+        },
+        admin_id,
+        pg,
     )
-    .fetch_optional(&pg)
-    .await?
-    .map(|r| r.secret) else {
+    .await
+}
+
+/// A student signing themselves in or out from an enrolled device, using the
+/// code on a display in place of a kiosk
+#[tracing::instrument(skip(pg, token), err)]
+pub(super) async fn self_route(
+    SelfRequest {
+        issuer,
+        totp,
+        token,
+        kind,
+        action,
+        location,
+        summary,
+    }: SelfRequest,
+    pg: PgPool,
+) -> Result<Response, SwipeError> {
+    verify(secret(&issuer, &kind, &pg).await?, &totp, None)?;
+
+    let Some((device_id, sid_hashed)) = device::authenticate(&token, &pg).await? else {
         return Err(SwipeError::unauthorized());
     };
 
-    let verifier = TOTP::new(Algorithm::SHA1, 6, 1, 30, secret)?;
-    if !verifier.check_current(&totp).unwrap_or(false) {
-        return Err(SwipeError::unauthorized());
+    let (Issuer { admin_id, kiosk_id }, flagged) =
+        authorize(issuer, &kind, location.as_ref(), &pg).await?;
+
+    act(
+        Swipe {
+            sid_hashed,
+            kind,
+            // students can't override debouncing themselves
+            force: false,
+            action,
+            at: Utc::now(),
+            location,
+            flagged,
+            device_id: Some(device_id),
+            kiosk_id,
+            summary,
+        },
+        admin_id,
+        pg,
+    )
+    .await
+}
+
+impl BatchResult {
+    fn new(id: String, result: Result<Response, SwipeError>) -> Self {
+        let (acknowledged, action, fallthrough, error) = match result {
+            Ok(Response::Acted(action) | Response::Summary(Summary { action, .. })) => {
+                (true, Some(action), None, None)
+            }
+            Ok(Response::Fallthrough(fallthrough)) => (true, None, Some(fallthrough), None),
+            Err(SwipeError::InternalServerError(PlainText(err))) => (false, None, None, Some(err)),
+            Err(
+                SwipeError::BadRequest(PlainText(err))
+                | SwipeError::Unauthorized(PlainText(err))
+                | SwipeError::Forbidden(PlainText(err))
+                | SwipeError::NotFound(PlainText(err))
+                | SwipeError::Closed(PlainText(err))
+                | SwipeError::UnknownBadge(PlainText(err)),
+            ) => (true, None, None, Some(err)),
+        };
+
+        Self {
+            id,
+            acknowledged,
+            action,
+            fallthrough,
+            error,
+        }
     }
+}
 
-    let claims = jwt::Claims::new(issuer, jwt::Claims::EXPIRY, &pg).await?;
-    claims.perms.assert(Permission::Roster)?;
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn batch(
+    BatchRequest { issuer, mut swipes }: BatchRequest,
+    pg: PgPool,
+) -> Result<BatchResponse, SwipeError> {
+    let Issuer { admin_id, kiosk_id } = resolve(issuer.clone(), &pg).await?;
+
+    swipes.sort_by_key(|swipe| swipe.timestamp);
+
+    let mut secrets = HashMap::new();
+    let mut results = Vec::with_capacity(swipes.len());
+
+    for item in swipes {
+        let id = item.id.clone();
+        let result = batch_item(
+            item,
+            &issuer,
+            &admin_id,
+            kiosk_id.as_deref(),
+            &mut secrets,
+            &pg,
+        )
+        .await;
+        results.push(BatchResult::new(id, result));
+    }
+
+    Ok(BatchResponse { results })
+}
+
+async fn batch_item(
+    BatchItem {
+        id: _,
+        timestamp,
+        totp,
+        sid_hashed,
+        kind,
+        force,
+        action,
+        location,
+    }: BatchItem,
+    issuer: &str,
+    admin_id: &str,
+    kiosk_id: Option<&str>,
+    secrets: &mut HashMap<HourType, Vec<u8>>,
+    pg: &PgPool,
+) -> Result<Response, SwipeError> {
+    let now = Utc::now();
+    if timestamp > now + MAX_CLOCK_DRIFT || timestamp < now - MAX_QUEUE_AGE {
+        return Err(SwipeError::timestamp());
+    }
+
+    let secret = match secrets.get(&kind) {
+        Some(secret) => secret.clone(),
+        None => {
+            let secret = secret(issuer, &kind, pg).await?;
+            secrets.insert(kind.clone(), secret.clone());
+            secret
+        }
+    };
+
+    verify(secret, &totp, Some(timestamp))?;
 
-    if !kind.allowed(&pg).await? {
-        return Err(SwipeError::hour_type(kind));
+    if !kind
+        .allowed_at(timestamp.and_local().date_naive(), pg)
+        .await?
+    {
+        return Err(SwipeError::hour_type(kind.clone()));
     }
 
+    let flagged = locate(&kind, location.as_ref(), pg).await?;
+
+    // the kiosk may resend a swipe whose result it never received
+    let replayed = sqlx::query!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM records
+            WHERE sid_hashed = $1
+                AND hour_type = $2
+                AND (sign_in = $3 OR sign_out = $3)
+        ) AS "exists!"
+        "#,
+        sid_hashed,
+        kind.as_str(),
+        timestamp,
+    )
+    .fetch_one(pg)
+    .await?
+    .exists;
+
+    if replayed {
+        return Ok(Response::Fallthrough(SwipeFallthrough::Ignored));
+    }
+
+    act(
+        Swipe {
+            sid_hashed,
+            kind,
+            force,
+            action,
+            at: timestamp,
+            location,
+            flagged,
+            device_id: None,
+            kiosk_id: kiosk_id.map(str::to_string),
+            // the result goes to the kiosk's queue, not to the student
+            summary: false,
+        },
+        admin_id.to_string(),
+        pg.clone(),
+    )
+    .await
+}
+
+/// A single, already-authenticated swipe
+#[derive(Debug)]
+pub(super) struct Swipe {
+    pub(super) sid_hashed: String,
+    pub(super) kind: HourType,
+    pub(super) force: bool,
+    pub(super) action: Option<SwipeAction>,
+    /// When the swipe happened
+    pub(super) at: chrono::DateTime<Utc>,
+    pub(super) location: Option<Location>,
+    /// Whether the swipe failed a geofence check, and should be flagged
+    pub(super) flagged: bool,
+    /// Set if the student swiped from their own enrolled device
+    pub(super) device_id: Option<String>,
+    /// Set if the swipe was taken by, or shown on, a registered kiosk
+    pub(super) kiosk_id: Option<String>,
+    /// Respond with a [`Summary`] instead of just the action
+    pub(super) summary: bool,
+}
+
+/// Summarize the student's progress in `kind` as of `at`, given the length of
+/// the session just signed out of, if any
+async fn summarize(
+    action: SwipeAction,
+    session: Option<chrono::Duration>,
+    sid_hashed: &str,
+    kind: &HourType,
+    at: chrono::DateTime<Utc>,
+    pg: &PgPool,
+) -> Result<Summary, sqlx::Error> {
+    let policy = kind.session_policy(pg).await?;
+    let today = at.and_local().date_naive();
+
+    // wide enough to cover any UTC offset, narrowed to the local day below
+    let today_hours = sqlx::query!(
+        r#"
+        SELECT sign_in, sign_out AS "sign_out!"
+        FROM records
+        WHERE sid_hashed = $1
+            AND hour_type = $2
+            AND sign_out IS NOT NULL
+            AND sign_in BETWEEN $3::timestamptz - INTERVAL '1 day'
+                AND $3::timestamptz + INTERVAL '1 day'
+        "#,
+        sid_hashed,
+        kind.as_str(),
+        at,
+    )
+    .fetch_all(pg)
+    .await?
+    .into_iter()
+    .filter(|r| r.sign_in.and_local().date_naive() == today)
+    .map(|r| policy.credit(r.sign_out - r.sign_in))
+    .sum();
+
+    let season = season::resolve(None, pg).await?;
+
+    let season_hours = match &season {
+        Some(season) => Some(
+            hours::totals(sid_hashed, season, pg)
+                .await?
+                .get(kind)
+                .copied()
+                .unwrap_or_default(),
+        ),
+        None => None,
+    };
+
+    let goal = kind
+        .season_goal(season.as_ref().map(|s| s.id.as_str()), pg)
+        .await?
+        .unwrap_or_default();
+
+    Ok(Summary {
+        action,
+        session_seconds: session.map(|dt| dt.num_seconds()),
+        session_hours: session.map(|dt| policy.credit(dt)),
+        today_hours,
+        season_hours,
+        goal,
+    })
+}
+
+/// Respond to a swipe that asked for a summary. The swipe has already gone
+/// through, so if the summary can't be built, fall back to the bare action
+/// rather than having the kiosk retry it.
+async fn respond(
+    action: SwipeAction,
+    session: Option<chrono::Duration>,
+    sid_hashed: &str,
+    kind: &HourType,
+    at: chrono::DateTime<Utc>,
+    pg: &PgPool,
+) -> Response {
+    match summarize(action, session, sid_hashed, kind, at, pg).await {
+        Ok(summary) => Response::Summary(summary),
+        Err(e) => {
+            error!("Failed to summarize swipe: {e}");
+            Response::Acted(action)
+        }
+    }
+}
+
+/// Run a swipe through the login/logout state machine, as of `swipe.at`.
+///
+/// Authentication and hour type availability must already be checked. Swipe
+/// windows and blackouts are checked here, since they only stop sign-ins, so
+/// students can still swipe out once a window has ended.
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn act(
+    Swipe {
+        sid_hashed,
+        kind,
+        force,
+        action,
+        at,
+        location,
+        flagged,
+        device_id,
+        kiosk_id,
+        summary,
+    }: Swipe,
+    admin_id: String,
+    pg: PgPool,
+) -> Result<Response, SwipeError> {
     let records = sqlx::query!(
         r#"
-        SELECT id, sign_in FROM records
+        SELECT id, sign_in, multi_day FROM records
         WHERE sid_hashed = $1
             AND hour_type = $2
             AND sign_out IS NULL
         "#,
         sid_hashed,
-        kind as HourType,
+        kind.as_str(),
     )
     .fetch_all(&pg)
     .await?;
 
-    let now = Local::now();
+    let policy = kind.session_policy(&pg).await?;
 
     let record = records
         .into_iter()
-        .find(|r| r.sign_in.and_local().date_naive() == now.date_naive());
+        .find(|r| policy.open_at(r.multi_day, r.sign_in, at));
 
     if let Some(record) = record {
         if let Some(SwipeAction::Login) = action {
             return Ok(Response::Fallthrough(SwipeFallthrough::Ignored));
         }
 
-        let dt = now - record.sign_in.and_local();
+        let dt = at - record.sign_in;
 
-        if (dt.num_minutes() < 3) && !force {
+        // replayed swipes may be older than the sign-in they would close
+        if dt <= chrono::Duration::zero() {
+            return Ok(Response::Fallthrough(SwipeFallthrough::Ignored));
+        }
+
+        if policy.debounced(dt) && !force {
             return Ok(Response::Fallthrough(SwipeFallthrough::Denied));
         }
 
         sqlx::query!(
             r#"
             UPDATE records
-            SET sign_out = NOW()
+            SET sign_out = $2, flagged = flagged OR $3
             WHERE id = $1
             "#,
-            record.id
+            record.id,
+            at,
+            flagged,
         )
         .execute(&pg)
         .await?;
 
+        let response = if summary {
+            respond(SwipeAction::Logout, Some(dt), &sid_hashed, &kind, at, &pg).await
+        } else {
+            Response::Acted(SwipeAction::Logout)
+        };
+
         tokio::spawn(async move {
             telemeter(
                 StudentLogout {
                     sid_hashed,
                     record_id: record.id,
-                    admin_id: claims.sub,
+                    admin_id,
+                    flagged,
+                    location,
+                    device_id,
+                    kiosk_id,
                 },
                 &pg,
             )
@@ -204,18 +852,20 @@ pub(super) async fn route(
             .log();
         });
 
-        return Ok(Response::Acted(SwipeAction::Logout));
+        return Ok(response);
     }
 
     if let Some(SwipeAction::Logout) = action {
         return Ok(Response::Fallthrough(SwipeFallthrough::Ignored));
     }
 
+    open(&kind, at, &pg).await?;
+
     let id = cuid2();
     let q = sqlx::query!(
         r#"
-        INSERT INTO records (id, sid_hashed, hour_type, sign_in)
-        SELECT $1, $2, $3, NOW()
+        INSERT INTO records (id, sid_hashed, hour_type, sign_in, flagged)
+        SELECT $1, $2, $3, $4, $5
         WHERE EXISTS (
             SELECT 1
             FROM students s
@@ -224,7 +874,9 @@ pub(super) async fn route(
         "#,
         id,
         sid_hashed,
-        kind as HourType,
+        kind.as_str(),
+        at,
+        flagged,
     )
     .execute(&pg)
     .await?;
@@ -233,12 +885,22 @@ pub(super) async fn route(
         return Err(SwipeError::not_found());
     }
 
+    let response = if summary {
+        respond(SwipeAction::Login, None, &sid_hashed, &kind, at, &pg).await
+    } else {
+        Response::Acted(SwipeAction::Login)
+    };
+
     tokio::spawn(async move {
         telemeter(
             StudentLogin {
                 sid_hashed,
                 record_id: id,
-                admin_id: claims.sub,
+                admin_id,
+                flagged,
+                location,
+                device_id,
+                kiosk_id,
             },
             &pg,
         )
@@ -246,5 +908,5 @@ pub(super) async fn route(
         .log();
     });
 
-    Ok(Response::Acted(SwipeAction::Login))
+    Ok(response)
 }
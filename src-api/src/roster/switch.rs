@@ -0,0 +1,168 @@
+use std::collections::HashSet;
+
+use crate::{prelude::*, roster::hour_type::HourTypeError};
+
+#[derive(Object, Debug)]
+#[oai(rename = "SwitchRequest")]
+pub(super) struct Request {
+    from: HourType,
+    to: HourType,
+    /// Only switch this student. If null, switches everyone signed in to
+    /// `from`.
+    sid_hashed: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Object)]
+pub(crate) struct Switched {
+    pub(crate) sid_hashed: String,
+    /// The record of the old hour type, now signed out
+    pub(crate) closed_id: String,
+    /// The record of the new hour type, or null if the student was already
+    /// signed in to it
+    pub(crate) opened_id: Option<String>,
+}
+
+#[derive(Object)]
+#[oai(rename = "SwitchResponse")]
+pub(super) struct Response {
+    switched: Vec<Switched>,
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError, HourTypeError)]
+pub(super) enum Error {
+    /// `to` is not allowed at this time, or is the same as `from`
+    #[oai(status = 400)]
+    #[construct(hour_type(HourType), "{source} hours are not allowed right now")]
+    #[construct(same, "from and to must differ")]
+    BadRequest(PlainText<String>),
+
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+/// Sign out of every matching open record of one hour type and sign in to
+/// another in its place, at the same instant
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn route(
+    Request {
+        from,
+        to,
+        sid_hashed,
+    }: Request,
+    admin_id: String,
+    pg: PgPool,
+) -> Result<Response, Error> {
+    if from == to {
+        return Err(Error::same());
+    }
+
+    if !to.allowed(&pg).await? {
+        return Err(Error::hour_type(to.clone()));
+    }
+
+    let from_policy = from.session_policy(&pg).await?;
+    let to_policy = to.session_policy(&pg).await?;
+    let at = Utc::now();
+
+    let mut tx = pg.begin().await?;
+
+    let open = sqlx::query!(
+        r#"
+        SELECT id, sid_hashed, hour_type AS "hour_type: HourType", sign_in, multi_day
+        FROM records
+        WHERE hour_type IN ($1, $2)
+            AND sign_out IS NULL
+            AND ($3::text IS NULL OR sid_hashed = $3)
+        FOR UPDATE
+        "#,
+        from.as_str(),
+        to.as_str(),
+        sid_hashed,
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    // students already signed in to `to` just get signed out of `from`
+    let already = open
+        .iter()
+        .filter(|r| r.hour_type == to && to_policy.open_at(r.multi_day, r.sign_in, at))
+        .map(|r| r.sid_hashed.clone())
+        .collect::<HashSet<_>>();
+
+    let mut switched = vec![];
+
+    for record in open {
+        if record.hour_type != from || !from_policy.open_at(record.multi_day, record.sign_in, at) {
+            continue;
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE records
+            SET sign_out = $2
+            WHERE id = $1
+            "#,
+            record.id,
+            at,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let opened_id = if already.contains(&record.sid_hashed) {
+            None
+        } else {
+            let id = cuid2();
+
+            sqlx::query!(
+                r#"
+                INSERT INTO records (id, sid_hashed, hour_type, sign_in)
+                VALUES ($1, $2, $3, $4)
+                "#,
+                id,
+                record.sid_hashed,
+                to.as_str(),
+                at,
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            Some(id)
+        };
+
+        switched.push(Switched {
+            sid_hashed: record.sid_hashed,
+            closed_id: record.id,
+            opened_id,
+        });
+    }
+
+    tx.commit().await?;
+
+    if !switched.is_empty() {
+        let switched = switched.clone();
+        tokio::spawn(async move {
+            telemeter(
+                HourTypeSwitch {
+                    admin_id,
+                    from,
+                    to,
+                    at,
+                    switched,
+                },
+                &pg,
+            )
+            .await
+            .log();
+        });
+    }
+
+    Ok(Response { switched })
+}
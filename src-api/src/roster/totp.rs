@@ -49,7 +49,7 @@ pub(super) async fn route(
         WHERE admin_id = $1 AND hour_type = $2
         "#,
         admin_id,
-        hour_type as HourType
+        hour_type.as_str()
     )
     .fetch_optional(&pg)
     .await?
@@ -63,13 +63,12 @@ pub(super) async fn route(
                 r#"
                 INSERT INTO otps (admin_id, secret, hour_type)
                 VALUES ($1, $2, $3)
-                ON CONFLICT (admin_id) DO UPDATE
-                SET secret = EXCLUDED.secret,
-                    hour_type = EXCLUDED.hour_type
+                ON CONFLICT (admin_id, hour_type) DO UPDATE
+                SET secret = EXCLUDED.secret
                 "#,
                 admin_id,
                 bytes,
-                hour_type as HourType
+                hour_type.as_str()
             )
             .execute(&pg)
             .await?;
@@ -0,0 +1,103 @@
+use futures_util::future::{BoxFuture, FutureExt};
+
+use crate::{prelude::*, roster::auto_close, trash::purge};
+
+/// A recurring maintenance task
+pub(super) struct Job {
+    /// Key in `scheduled_jobs`
+    pub(super) name: &'static str,
+    /// Interval used until an admin changes it
+    pub(super) default_interval: i32,
+    pub(super) run: fn(&PgPool) -> BoxFuture<'_, Result<(), sqlx::Error>>,
+}
+
+pub(super) const JOBS: &[Job] = &[
+    Job {
+        name: "prune_login_sessions",
+        default_interval: 5 * 60,
+        run: |pg| prune_login_sessions(pg).boxed(),
+    },
+    Job {
+        name: "expire_invites",
+        default_interval: 60 * 60,
+        run: |pg| expire_invites(pg).boxed(),
+    },
+    Job {
+        name: "prune_register_sessions",
+        default_interval: 60 * 60,
+        run: |pg| prune_register_sessions(pg).boxed(),
+    },
+    Job {
+        name: "prune_stream_filters",
+        default_interval: 10 * 60,
+        run: |pg| prune_stream_filters(pg).boxed(),
+    },
+    Job {
+        name: "auto_close",
+        default_interval: 5 * 60,
+        run: |pg| auto_close::sweep(pg).boxed(),
+    },
+    Job {
+        name: "purge_trash",
+        default_interval: 60 * 60,
+        run: |pg| purge::sweep(pg).boxed(),
+    },
+];
+
+pub(super) fn find(name: &str) -> Option<&'static Job> {
+    JOBS.iter().find(|job| job.name == name)
+}
+
+/// Login sessions only live for the length of the SRP handshake
+async fn prune_login_sessions(pg: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM login_sessions
+        WHERE created_at < NOW() - INTERVAL '10 minutes'
+        "#
+    )
+    .execute(pg)
+    .await?;
+
+    Ok(())
+}
+
+async fn expire_invites(pg: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM invites
+        WHERE expiry < NOW()
+        "#
+    )
+    .execute(pg)
+    .await?;
+
+    Ok(())
+}
+
+async fn prune_register_sessions(pg: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM register_sessions
+        WHERE expiry < NOW()
+        "#
+    )
+    .execute(pg)
+    .await?;
+
+    Ok(())
+}
+
+/// Filters are refreshed by their stream while it's open
+async fn prune_stream_filters(pg: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM telemetry_streams
+        WHERE updated_at < NOW() - INTERVAL '1 hour'
+        "#
+    )
+    .execute(pg)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+mod jobs;
+mod run;
+mod status;
+
+pub(crate) use run::spawn;
+
+use crate::prelude::*;
+
+/// Recurring maintenance, e.g. pruning expired sessions and auto-closing
+/// records. Each job runs on at most one replica at a time.
+pub(crate) struct SchedulerService {
+    pg: PgPool,
+}
+
+impl SchedulerService {
+    pub(crate) fn new(pg: PgPool) -> Self {
+        Self { pg }
+    }
+}
+
+#[auto_operation_ids]
+#[OpenApi(tag = "Tag::Scheduler", prefix_path = "/scheduler")]
+impl SchedulerService {
+    #[oai(path = "/", method = "get")]
+    async fn query_many(&self, jwt: Jwt) -> Result<Json<status::QueryManyResponse>, status::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::AdminView)?;
+
+        Ok(Json(status::query_many(self.pg.clone()).await?))
+    }
+
+    #[oai(path = "/:name", method = "patch")]
+    async fn update(
+        &self,
+        name: Path<String>,
+        request: Json<status::UpdateRequest>,
+        jwt: Jwt,
+    ) -> Result<(), status::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::AdminEdit)?;
+
+        status::update(name.0, request.0, self.pg.clone()).await
+    }
+}
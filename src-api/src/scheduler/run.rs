@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use super::jobs::{JOBS, Job};
+use crate::prelude::*;
+
+/// How often jobs are checked for being due. Intervals are effectively rounded
+/// up to a multiple of this.
+const TICK: Duration = Duration::from_secs(30);
+
+/// First key of every job's advisory lock, so they can't collide with other
+/// users of advisory locks
+const LOCK_NAMESPACE: i32 = 0x6a6f6273;
+
+/// Add any new jobs with their default interval.
+///
+/// Rows for jobs this build doesn't know about are kept, since during a
+/// rolling deploy they may belong to a newer replica.
+async fn register(pg: &PgPool) -> Result<(), sqlx::Error> {
+    let names = JOBS
+        .iter()
+        .map(|job| job.name.to_owned())
+        .collect::<Vec<_>>();
+    let intervals = JOBS
+        .iter()
+        .map(|job| job.default_interval)
+        .collect::<Vec<_>>();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO scheduled_jobs (name, interval_seconds)
+        SELECT * FROM UNNEST($1::text[], $2::int[])
+        ON CONFLICT (name) DO NOTHING
+        "#,
+        &names,
+        &intervals,
+    )
+    .execute(pg)
+    .await?;
+
+    Ok(())
+}
+
+/// Run `job` if it's due and no other replica is running it
+async fn run(job: &Job, pg: &PgPool) -> Result<(), sqlx::Error> {
+    let mut conn = pg.acquire().await?;
+
+    // session-level, so it's held for as long as the job runs, and released
+    // if this replica dies
+    let locked = sqlx::query_scalar!(
+        r#"SELECT pg_try_advisory_lock($1, hashtext($2)) AS "locked!""#,
+        LOCK_NAMESPACE,
+        job.name,
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if !locked {
+        return Ok(());
+    }
+
+    let result = claim_and_run(job, pg).await;
+
+    let unlocked = sqlx::query_scalar!(
+        r#"SELECT pg_advisory_unlock($1, hashtext($2)) AS "unlocked!""#,
+        LOCK_NAMESPACE,
+        job.name,
+    )
+    .fetch_one(&mut *conn)
+    .await;
+
+    // never hand a connection still holding the lock back to the pool
+    if !matches!(unlocked, Ok(true)) {
+        conn.close_on_drop();
+    }
+
+    result
+}
+
+async fn claim_and_run(job: &Job, pg: &PgPool) -> Result<(), sqlx::Error> {
+    // checked under the lock, so a replica that was waiting on the lock can't
+    // run the job again right after another one finishes it
+    let claimed = sqlx::query_scalar!(
+        r#"
+        UPDATE scheduled_jobs
+        SET last_started_at = NOW()
+        WHERE name = $1
+            AND enabled
+            AND (
+                last_started_at IS NULL
+                OR last_started_at <= NOW() - make_interval(secs => interval_seconds)
+            )
+        RETURNING name
+        "#,
+        job.name,
+    )
+    .fetch_optional(pg)
+    .await?;
+
+    if claimed.is_none() {
+        return Ok(());
+    }
+
+    debug!(job = job.name, "running scheduled job");
+
+    let result = (job.run)(pg).await;
+
+    if let Err(e) = &result {
+        error!(job = job.name, "scheduled job failed: {e}");
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE scheduled_jobs
+        SET last_finished_at = NOW(), last_error = $2
+        WHERE name = $1
+        "#,
+        job.name,
+        result.as_ref().err().map(ToString::to_string),
+    )
+    .execute(pg)
+    .await?;
+
+    Ok(())
+}
+
+/// Spawn the scheduler, which runs every due job in turn on each tick.
+///
+/// Jobs run one at a time so the scheduler never holds more than two pool
+/// connections.
+pub(crate) fn spawn(pg: PgPool) {
+    tokio::spawn(async move {
+        register(&pg).await.log();
+
+        let mut interval = tokio::time::interval(TICK);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+
+            for job in JOBS {
+                run(job, &pg).await.log();
+            }
+        }
+    });
+}
@@ -0,0 +1,133 @@
+use super::jobs;
+use crate::prelude::*;
+
+/// Shortest interval a job can be set to, so maintenance can't starve the pool
+const MIN_INTERVAL: i32 = 60;
+
+#[derive(Object, Debug)]
+pub(super) struct JobStatus {
+    name: String,
+    interval_seconds: i32,
+    /// Disabled jobs are never run
+    enabled: bool,
+    last_started_at: Option<chrono::DateTime<Utc>>,
+    last_finished_at: Option<chrono::DateTime<Utc>>,
+    /// Why the last run failed. Null if it succeeded, or the job hasn't run.
+    last_error: Option<String>,
+    /// Started, and not yet finished
+    running: bool,
+    /// When the job next becomes due. Null if disabled.
+    next_run_at: Option<chrono::DateTime<Utc>>,
+}
+
+#[derive(Object)]
+#[oai(rename = "JobQueryManyResponse")]
+pub(super) struct QueryManyResponse {
+    jobs: Vec<JobStatus>,
+}
+
+#[derive(Object, Debug)]
+#[oai(rename = "JobUpdateRequest")]
+pub(super) struct UpdateRequest {
+    interval_seconds: Option<i32>,
+    enabled: Option<bool>,
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum Error {
+    /// The interval is shorter than a minute
+    #[oai(status = 400)]
+    #[construct(interval, "interval_seconds must be at least 60")]
+    BadRequest(PlainText<String>),
+
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    /// No job with the given name exists
+    #[oai(status = 404)]
+    #[construct("Job not found")]
+    NotFound(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn query_many(pg: PgPool) -> Result<QueryManyResponse, Error> {
+    // other replicas may have registered jobs this build doesn't know about
+    let names = jobs::JOBS
+        .iter()
+        .map(|job| job.name.to_owned())
+        .collect::<Vec<_>>();
+
+    let jobs = sqlx::query_as!(
+        JobStatus,
+        r#"
+        SELECT
+            name,
+            interval_seconds,
+            enabled,
+            last_started_at,
+            last_finished_at,
+            last_error,
+            COALESCE(last_started_at > last_finished_at, last_started_at IS NOT NULL)
+                AS "running!",
+            CASE WHEN enabled THEN
+                COALESCE(last_started_at + make_interval(secs => interval_seconds), NOW())
+            END AS next_run_at
+        FROM scheduled_jobs
+        WHERE name = ANY($1)
+        ORDER BY name
+        "#,
+        &names,
+    )
+    .fetch_all(&pg)
+    .await?;
+
+    Ok(QueryManyResponse { jobs })
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn update(
+    name: String,
+    UpdateRequest {
+        interval_seconds,
+        enabled,
+    }: UpdateRequest,
+    pg: PgPool,
+) -> Result<(), Error> {
+    if jobs::find(&name).is_none() {
+        return Err(Error::not_found());
+    }
+
+    if interval_seconds.is_some_and(|i| i < MIN_INTERVAL) {
+        return Err(Error::interval());
+    }
+
+    let q = sqlx::query!(
+        r#"
+        UPDATE scheduled_jobs
+        SET
+            interval_seconds = COALESCE($2, interval_seconds),
+            enabled = COALESCE($3, enabled)
+        WHERE name = $1
+        "#,
+        name,
+        interval_seconds,
+        enabled,
+    )
+    .execute(&pg)
+    .await?;
+
+    // registered by the scheduler on startup, so it may not be there yet
+    if q.rows_affected() == 0 {
+        return Err(Error::not_found());
+    }
+
+    Ok(())
+}
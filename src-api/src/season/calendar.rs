@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, NaiveDate};
+
+use crate::{cache::Cached, prelude::*};
+
+/// Key dates of a single year, as stored. Unset dates fall back to defaults.
+///
+/// Every date must fall within the year it is configured for.
+#[derive(Object, Debug, Clone, Copy, Default)]
+pub(crate) struct CalendarConfig {
+    /// Defaults to the first Saturday of January, or the second if January 1st
+    /// is a Thursday, Friday or Saturday
+    pub kickoff: Option<NaiveDate>,
+    /// Defaults to April 30th
+    pub build_ends: Option<NaiveDate>,
+    /// Defaults to September 1st. Learning runs until the day before kickoff.
+    pub learning_starts: Option<NaiveDate>,
+    /// Defaults to the day after build season ends
+    pub offseason_starts: Option<NaiveDate>,
+    /// Defaults to the day before kickoff
+    pub offseason_ends: Option<NaiveDate>,
+}
+
+/// Key dates of a single year, with defaults filled in
+#[derive(Object, Debug, Clone, Copy)]
+pub(crate) struct Calendar {
+    pub year: i32,
+    pub kickoff: NaiveDate,
+    pub build_ends: NaiveDate,
+    pub learning_starts: NaiveDate,
+    pub offseason_starts: NaiveDate,
+    pub offseason_ends: NaiveDate,
+}
+
+#[derive(Object, Debug)]
+pub(super) struct HourTypeWindow {
+    hour_type: HourType,
+    /// Inclusive
+    starts: NaiveDate,
+    /// Inclusive. If before `starts`, the window wraps around the end of the
+    /// year.
+    ends: NaiveDate,
+}
+
+#[derive(Object)]
+#[oai(rename = "SeasonCalendarResponse")]
+pub(super) struct Response {
+    /// Dates configured for this year
+    config: CalendarConfig,
+    /// Dates in effect for this year
+    calendar: Calendar,
+    /// When each hour type is allowed this year
+    windows: Vec<HourTypeWindow>,
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum Error {
+    #[oai(status = 400)]
+    #[construct(year, "Year must be between 2000 and 9999")]
+    #[construct(
+        dates,
+        "Dates must fall within the given year, and build season must not end before kickoff"
+    )]
+    BadRequest(PlainText<String>),
+
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+/// Best guess at the date of kickoff in `year`
+fn kickoff_day(year: i32) -> NaiveDate {
+    // who tf knows when this will change but until first decides to give me an api
+    // for ts its staying this way
+    //
+    // ok, so, to find the date of kickoff, we can use the fact that its always the
+    // first saturday **unless** Jan 1st is a Thursday, Friday, or Saturday,
+    // in which case its the second saturday
+
+    // get new years day information
+    let nyd = chrono::NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+    let nydotw = nyd.weekday();
+
+    // postpone iff nyd is thurs, fri, sat
+    let postpone = nydotw.num_days_from_sunday() >= 4;
+    let postpone = if postpone { 7 } else { 0 };
+
+    // calculate kickoff day
+    let days2sat = 6 - nydotw.days_since(chrono::Weekday::Sun);
+
+    nyd + chrono::Duration::days(i64::from(days2sat) + postpone)
+}
+
+fn valid_year(year: i32) -> bool {
+    (2000..=9999).contains(&year)
+}
+
+impl CalendarConfig {
+    fn valid(&self, year: i32) -> bool {
+        let dates = [
+            self.kickoff,
+            self.build_ends,
+            self.learning_starts,
+            self.offseason_starts,
+            self.offseason_ends,
+        ];
+
+        dates.into_iter().flatten().all(|date| date.year() == year)
+    }
+
+    async fn fetch(year: i32, pg: &PgPool) -> Result<Self, sqlx::Error> {
+        static CACHE: Cached<HashMap<i32, CalendarConfig>> = Cached::new();
+
+        let configs = CACHE
+            .get(|| async {
+                let rows = sqlx::query!(
+                    r#"
+                    SELECT
+                        year,
+                        kickoff,
+                        build_ends,
+                        learning_starts,
+                        offseason_starts,
+                        offseason_ends
+                    FROM season_calendars
+                    "#,
+                )
+                .fetch_all(pg)
+                .await?;
+
+                Ok::<_, sqlx::Error>(
+                    rows.into_iter()
+                        .map(|row| {
+                            (
+                                row.year,
+                                CalendarConfig {
+                                    kickoff: row.kickoff,
+                                    build_ends: row.build_ends,
+                                    learning_starts: row.learning_starts,
+                                    offseason_starts: row.offseason_starts,
+                                    offseason_ends: row.offseason_ends,
+                                },
+                            )
+                        })
+                        .collect(),
+                )
+            })
+            .await?;
+
+        Ok(configs.get(&year).copied().unwrap_or_default())
+    }
+
+    fn resolve(self, year: i32) -> Calendar {
+        let kickoff = self.kickoff.unwrap_or_else(|| kickoff_day(year));
+        let build_ends = self
+            .build_ends
+            .unwrap_or_else(|| NaiveDate::from_ymd_opt(year, 4, 30).unwrap());
+
+        Calendar {
+            year,
+            kickoff,
+            build_ends,
+            learning_starts: self
+                .learning_starts
+                .unwrap_or_else(|| NaiveDate::from_ymd_opt(year, 9, 1).unwrap()),
+            offseason_starts: self
+                .offseason_starts
+                .unwrap_or(build_ends + chrono::Duration::days(1)),
+            offseason_ends: self
+                .offseason_ends
+                .unwrap_or(kickoff - chrono::Duration::days(1)),
+        }
+    }
+}
+
+impl Calendar {
+    /// The calendar in effect for `year`
+    pub(crate) async fn resolve(year: i32, pg: &PgPool) -> Result<Self, sqlx::Error> {
+        Ok(CalendarConfig::fetch(year, pg).await?.resolve(year))
+    }
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn query(year: i32, pg: PgPool) -> Result<Response, Error> {
+    if !valid_year(year) {
+        return Err(Error::year());
+    }
+
+    let config = CalendarConfig::fetch(year, &pg).await?;
+    let calendar = config.resolve(year);
+
+    let windows = HourType::windows(&calendar, &pg)
+        .await?
+        .into_iter()
+        .map(|(hour_type, (starts, ends))| HourTypeWindow {
+            hour_type,
+            starts,
+            ends,
+        })
+        .collect();
+
+    Ok(Response {
+        config,
+        calendar,
+        windows,
+    })
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn update(year: i32, config: CalendarConfig, pg: PgPool) -> Result<(), Error> {
+    if !valid_year(year) {
+        return Err(Error::year());
+    }
+
+    let calendar = config.resolve(year);
+
+    if !config.valid(year) || calendar.build_ends < calendar.kickoff {
+        return Err(Error::dates());
+    }
+
+    let CalendarConfig {
+        kickoff,
+        build_ends,
+        learning_starts,
+        offseason_starts,
+        offseason_ends,
+    } = config;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO season_calendars (
+            year,
+            kickoff,
+            build_ends,
+            learning_starts,
+            offseason_starts,
+            offseason_ends
+        )
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (year) DO UPDATE
+        SET kickoff = EXCLUDED.kickoff,
+            build_ends = EXCLUDED.build_ends,
+            learning_starts = EXCLUDED.learning_starts,
+            offseason_starts = EXCLUDED.offseason_starts,
+            offseason_ends = EXCLUDED.offseason_ends
+        "#,
+        year,
+        kickoff,
+        build_ends,
+        learning_starts,
+        offseason_starts,
+        offseason_ends,
+    )
+    .execute(&pg)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,241 @@
+use chrono::NaiveDate;
+
+use crate::prelude::*;
+
+/// A span of dates that hours, goals and attendance are scoped to
+#[derive(Clone, Debug, Serialize, Deserialize, Object)]
+pub(crate) struct Season {
+    pub id: String,
+    pub name: String,
+    /// Inclusive
+    pub starts: NaiveDate,
+    /// Inclusive, null while the season is ongoing
+    pub ends: Option<NaiveDate>,
+    /// Set once totals have been frozen by a rollover. Hours of archived
+    /// seasons no longer follow edits to their records.
+    pub archived_at: Option<chrono::DateTime<Utc>>,
+}
+
+#[derive(Object, Debug)]
+#[oai(rename = "SeasonCreateRequest")]
+pub(super) struct CreateRequest {
+    name: String,
+    starts: NaiveDate,
+    /// Omit for an ongoing season
+    ends: Option<NaiveDate>,
+}
+
+#[derive(Object, Debug)]
+#[oai(rename = "SeasonUpdateRequest")]
+pub(super) struct UpdateRequest {
+    #[oai(notnull)]
+    name: Option<String>,
+    /// Can't be changed once archived
+    #[oai(notnull)]
+    starts: Option<NaiveDate>,
+    /// Can't be changed once archived
+    #[oai(notnull)]
+    ends: Option<NaiveDate>,
+}
+
+#[derive(Object)]
+#[oai(rename = "SeasonQueryManyResponse")]
+pub(super) struct QueryManyResponse {
+    seasons: Vec<Season>,
+}
+
+#[derive(ApiResponse, ApiError)]
+pub(super) enum QueryManyError {
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum Error {
+    /// `name` is empty, or `ends` is before `starts`
+    #[oai(status = 400)]
+    #[construct(name, "name must not be empty")]
+    #[construct(range, "ends must not be before starts")]
+    BadRequest(PlainText<String>),
+
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    /// Season with the given ID does not exist
+    #[oai(status = 404)]
+    #[construct("Season not found")]
+    NotFound(PlainText<String>),
+
+    /// The season would overlap another, or its dates were changed after
+    /// being archived
+    #[oai(status = 409)]
+    #[construct(overlap, "Seasons must not overlap")]
+    #[construct(archived, "Dates of archived seasons can't be changed")]
+    Conflict(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+impl Season {
+    pub(crate) fn contains(&self, date: NaiveDate) -> bool {
+        date >= self.starts && self.ends.is_none_or(|ends| date <= ends)
+    }
+}
+
+/// Map exclusion violations, i.e. overlapping seasons, to a conflict
+fn overlap(err: sqlx::Error) -> Error {
+    match err {
+        sqlx::Error::Database(err) if err.code().as_deref() == Some("23P01") => Error::overlap(),
+        err => err.into(),
+    }
+}
+
+pub(super) async fn fetch(id: &str, pg: &PgPool) -> Result<Option<Season>, sqlx::Error> {
+    sqlx::query_as!(
+        Season,
+        r#"
+        SELECT id, name, starts, ends, archived_at
+        FROM seasons
+        WHERE id = $1
+        "#,
+        id,
+    )
+    .fetch_optional(pg)
+    .await
+}
+
+/// The season with the given ID, or if none is given, the latest season to
+/// have started
+pub(crate) async fn resolve(id: Option<&str>, pg: &PgPool) -> Result<Option<Season>, sqlx::Error> {
+    if let Some(id) = id {
+        return fetch(id, pg).await;
+    }
+
+    sqlx::query_as!(
+        Season,
+        r#"
+        SELECT id, name, starts, ends, archived_at
+        FROM seasons
+        WHERE starts <= $1
+        ORDER BY starts DESC
+        LIMIT 1
+        "#,
+        Local::now().date_naive(),
+    )
+    .fetch_optional(pg)
+    .await
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn query_many(pg: PgPool) -> Result<QueryManyResponse, QueryManyError> {
+    let seasons = sqlx::query_as!(
+        Season,
+        r#"
+        SELECT id, name, starts, ends, archived_at
+        FROM seasons
+        ORDER BY starts DESC
+        "#,
+    )
+    .fetch_all(&pg)
+    .await?;
+
+    Ok(QueryManyResponse { seasons })
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn add(
+    CreateRequest { name, starts, ends }: CreateRequest,
+    pg: PgPool,
+) -> Result<Season, Error> {
+    if name.trim().is_empty() {
+        return Err(Error::name());
+    }
+
+    if ends.is_some_and(|ends| ends < starts) {
+        return Err(Error::range());
+    }
+
+    sqlx::query_as!(
+        Season,
+        r#"
+        INSERT INTO seasons (id, name, starts, ends)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, name, starts, ends, archived_at
+        "#,
+        cuid2(),
+        name,
+        starts,
+        ends,
+    )
+    .fetch_one(&pg)
+    .await
+    .map_err(overlap)
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn update(
+    id: String,
+    UpdateRequest { name, starts, ends }: UpdateRequest,
+    pg: PgPool,
+) -> Result<Season, Error> {
+    let old = fetch(&id, &pg).await?.ok_or(Error::not_found())?;
+
+    if name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+        return Err(Error::name());
+    }
+
+    if old.archived_at.is_some() && (starts.is_some() || ends.is_some()) {
+        return Err(Error::archived());
+    }
+
+    if ends
+        .or(old.ends)
+        .is_some_and(|ends| ends < starts.unwrap_or(old.starts))
+    {
+        return Err(Error::range());
+    }
+
+    sqlx::query_as!(
+        Season,
+        r#"
+        UPDATE seasons
+        SET
+            name = COALESCE($2, name),
+            starts = COALESCE($3, starts),
+            ends = COALESCE($4, ends)
+        WHERE id = $1
+        RETURNING id, name, starts, ends, archived_at
+        "#,
+        id,
+        name,
+        starts,
+        ends,
+    )
+    .fetch_one(&pg) // checked for existence above
+    .await
+    .map_err(overlap)
+}
+
+/// Delete a season and any totals frozen for it. Records are left alone.
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn delete(id: String, pg: PgPool) -> Result<Season, Error> {
+    sqlx::query_as!(
+        Season,
+        r#"
+        DELETE FROM seasons
+        WHERE id = $1
+        RETURNING id, name, starts, ends, archived_at
+        "#,
+        id,
+    )
+    .fetch_optional(&pg)
+    .await?
+    .ok_or(Error::not_found())
+}
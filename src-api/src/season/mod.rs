@@ -0,0 +1,93 @@
+pub(crate) mod calendar;
+pub(crate) mod crud;
+mod rollover;
+
+pub(crate) use crud::{Season, resolve};
+
+use crate::prelude::*;
+
+pub(crate) struct SeasonService {
+    pg: PgPool,
+}
+
+impl SeasonService {
+    pub(crate) fn new(pg: PgPool) -> Self {
+        Self { pg }
+    }
+}
+
+#[auto_operation_ids]
+#[OpenApi(tag = "Tag::Season", prefix_path = "/season")]
+impl SeasonService {
+    #[oai(path = "/", method = "get")]
+    async fn query_many(&self) -> Result<Json<crud::QueryManyResponse>, crud::QueryManyError> {
+        Ok(Json(crud::query_many(self.pg.clone()).await?))
+    }
+
+    #[oai(path = "/", method = "post")]
+    async fn add(
+        &self,
+        request: Json<crud::CreateRequest>,
+        jwt: Jwt,
+    ) -> Result<Json<Season>, crud::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+
+        Ok(Json(crud::add(request.0, self.pg.clone()).await?))
+    }
+
+    #[oai(path = "/:id", method = "patch")]
+    async fn update(
+        &self,
+        id: Path<String>,
+        request: Json<crud::UpdateRequest>,
+        jwt: Jwt,
+    ) -> Result<Json<Season>, crud::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+
+        Ok(Json(crud::update(id.0, request.0, self.pg.clone()).await?))
+    }
+
+    #[oai(path = "/:id", method = "delete")]
+    async fn delete(&self, id: Path<String>, jwt: Jwt) -> Result<Json<Season>, crud::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+
+        Ok(Json(crud::delete(id.0, self.pg.clone()).await?))
+    }
+
+    #[oai(path = "/rollover", method = "post")]
+    async fn rollover(
+        &self,
+        request: Json<rollover::Request>,
+        jwt: Jwt,
+    ) -> Result<Json<rollover::Response>, rollover::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+
+        Ok(Json(
+            rollover::route(request.0, claims, self.pg.clone()).await?,
+        ))
+    }
+
+    #[oai(path = "/calendar/:year", method = "get")]
+    async fn calendar_query(
+        &self,
+        year: Path<i32>,
+    ) -> Result<Json<calendar::Response>, calendar::Error> {
+        Ok(Json(calendar::query(year.0, self.pg.clone()).await?))
+    }
+
+    #[oai(path = "/calendar/:year", method = "put")]
+    async fn calendar_update(
+        &self,
+        year: Path<i32>,
+        request: Json<calendar::CalendarConfig>,
+        jwt: Jwt,
+    ) -> Result<(), calendar::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(Permission::HoursEdit)?;
+        calendar::update(year.0, request.0, self.pg.clone()).await
+    }
+}
@@ -0,0 +1,157 @@
+use chrono::NaiveDate;
+
+use super::crud::Season;
+use crate::{prelude::*, roster::session::SessionPolicy, student::hours};
+
+#[derive(Object, Debug)]
+#[oai(rename = "SeasonRolloverRequest")]
+pub(super) struct Request {
+    /// Name of the new season
+    name: String,
+    /// First day of the new season, the ongoing season ends the day before.
+    /// Must be after the ongoing season starts, and no later than today.
+    starts: NaiveDate,
+}
+
+#[derive(Object)]
+#[oai(rename = "SeasonRolloverResponse")]
+pub(super) struct Response {
+    /// The season that just ended, with its totals frozen
+    archived: Season,
+    /// The season that just started
+    current: Season,
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum Error {
+    #[oai(status = 400)]
+    #[construct(name, "name must not be empty")]
+    #[construct(
+        starts,
+        "The new season must start after the ongoing season, and no later than today"
+    )]
+    BadRequest(PlainText<String>),
+
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    /// There is no season without an end date to roll over from
+    #[oai(status = 404)]
+    #[construct("No ongoing season")]
+    NotFound(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+/// End the ongoing season, freeze its totals and goals, and start a new one.
+/// No records are touched.
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn route(
+    Request { name, starts }: Request,
+    claims: jwt::Claims,
+    pg: PgPool,
+) -> Result<Response, Error> {
+    if name.trim().is_empty() {
+        return Err(Error::name());
+    }
+
+    let mut tx = pg.begin().await?;
+
+    let ongoing = sqlx::query_as!(
+        Season,
+        r#"
+        SELECT id, name, starts, ends, archived_at
+        FROM seasons
+        WHERE ends IS NULL
+        FOR UPDATE
+        "#,
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(Error::not_found())?;
+
+    if starts <= ongoing.starts || starts > Local::now().date_naive() {
+        return Err(Error::starts());
+    }
+
+    let archived = sqlx::query_as!(
+        Season,
+        r#"
+        UPDATE seasons
+        SET ends = $2, archived_at = NOW()
+        WHERE id = $1
+        RETURNING id, name, starts, ends, archived_at
+        "#,
+        ongoing.id,
+        starts - chrono::Duration::days(1),
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let policies = SessionPolicy::all(&pg).await?;
+
+    for (sid_hashed, totals) in hours::tally(None, &archived, &policies, &mut *tx).await? {
+        for (kind, hours) in totals {
+            sqlx::query!(
+                r#"
+                INSERT INTO season_totals (season_id, sid_hashed, hour_type, hours)
+                VALUES ($1, $2, $3, $4)
+                "#,
+                archived.id,
+                sid_hashed,
+                kind.as_str(),
+                hours,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO season_goals (season_id, hour_type, goal)
+        SELECT $1, id, goal FROM hour_types
+        "#,
+        archived.id,
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let current = sqlx::query_as!(
+        Season,
+        r#"
+        INSERT INTO seasons (id, name, starts)
+        VALUES ($1, $2, $3)
+        RETURNING id, name, starts, ends, archived_at
+        "#,
+        cuid2(),
+        name,
+        starts,
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let (archived_telemeter, current_telemeter) = (archived.clone(), current.clone());
+    tokio::spawn(async move {
+        telemeter(
+            SeasonRollover {
+                admin_id: claims.sub,
+                archived: archived_telemeter,
+                current: current_telemeter,
+            },
+            &pg,
+        )
+        .await
+        .log();
+    });
+
+    Ok(Response { archived, current })
+}
@@ -1,4 +1,4 @@
-use crate::{dbstream::Student, prelude::*};
+use crate::{dbstream::Student, prelude::*, trash};
 
 pub(super) type Response = Student;
 
@@ -27,18 +27,9 @@ pub(super) async fn route(
     claims: jwt::Claims,
     pg: PgPool,
 ) -> Result<Response, Error> {
-    let student = sqlx::query_as!(
-        Student,
-        r#"
-        DELETE FROM students
-        WHERE id_hashed = $1
-        RETURNING *
-        "#,
-        id_hashed,
-    )
-    .fetch_optional(&pg)
-    .await?
-    .ok_or(Error::not_found())?;
+    let student = trash::trash_student(&id_hashed, &claims.sub, &pg)
+        .await?
+        .ok_or(Error::not_found())?;
 
     let student_telemeter = student.clone();
     tokio::spawn(async move {
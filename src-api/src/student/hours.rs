@@ -1,33 +1,23 @@
-use crate::prelude::*;
-
-#[derive(Object, Default)]
-#[oai(rename = "StudentHoursResponse")]
-pub(super) struct Response {
-    build: f64,
-    learning: f64,
-    demo: f64,
-    offseason: f64,
-}
+use std::collections::{BTreeMap, HashMap};
 
-impl Response {
-    fn hours_mut(&mut self, kind: HourType) -> &mut f64 {
-        match kind {
-            HourType::Build => &mut self.build,
-            HourType::Learning => &mut self.learning,
-            HourType::Demo => &mut self.demo,
-            HourType::Offseason => &mut self.offseason,
-        }
-    }
+use chrono::{Days, NaiveTime};
 
-    fn add(&mut self, kind: HourType, amount: f64) {
-        *self.hours_mut(kind) += amount;
-    }
-}
+use crate::{
+    prelude::*,
+    roster::session::SessionPolicy,
+    season::{self, Season},
+};
+
+/// Hours by hour type ID. Every hour type is present, even if zero.
+pub(super) type Response = BTreeMap<String, f64>;
+
+/// Credited hours of a single student, by hour type
+pub(crate) type Totals = HashMap<HourType, f64>;
 
 #[derive(ApiResponse, ApiError)]
 pub(super) enum Error {
     #[oai(status = 404)]
-    #[construct("No completed records found")]
+    #[construct(season, "Season not found")]
     NotFound(PlainText<String>),
 
     #[oai(status = 500)]
@@ -35,29 +25,136 @@ pub(super) enum Error {
     InternalServerError(PlainText<String>),
 }
 
-#[tracing::instrument(skip(pg), err)]
-pub(super) async fn route(sid_hashed: String, pg: PgPool) -> Result<Response, Error> {
-    let mut res = Response::default();
+/// Live totals of every student with completed records or adjustments in
+/// `season`, or only of `sid_hashed` if given.
+///
+/// Records count towards the season their sign-in falls in, in server's local
+/// time.
+pub(crate) async fn tally(
+    sid_hashed: Option<&str>,
+    season: &Season,
+    policies: &HashMap<HourType, SessionPolicy>,
+    conn: &mut sqlx::PgConnection,
+) -> Result<HashMap<String, Totals>, sqlx::Error> {
+    // a day either side covers any UTC offset, `Season::contains` below is exact
+    let after = (season.starts - Days::new(1))
+        .and_time(NaiveTime::MIN)
+        .and_utc();
+    let before = season
+        .ends
+        .map(|ends| (ends + Days::new(2)).and_time(NaiveTime::MIN).and_utc());
 
     let records = sqlx::query!(
         r#"
-        SELECT hour_type AS "hour_type: HourType", sign_in, sign_out
+        SELECT sid_hashed, hour_type AS "hour_type: HourType", sign_in, sign_out
         FROM records
-        WHERE sid_hashed = $1 AND sign_out IS NOT NULL
+        WHERE ($1::text IS NULL OR sid_hashed = $1)
+            AND sign_out IS NOT NULL
+            AND sign_in >= $2
+            AND ($3::timestamptz IS NULL OR sign_in < $3)
         "#,
-        sid_hashed
+        sid_hashed,
+        after,
+        before,
     )
-    .fetch_all(&pg)
+    .fetch_all(&mut *conn)
     .await?;
 
-    if records.is_empty() {
-        return Err(Error::not_found());
-    }
+    let adjustments = sqlx::query!(
+        r#"
+        SELECT sid_hashed, hour_type AS "hour_type: HourType", hours
+        FROM hour_adjustments
+        WHERE ($1::text IS NULL OR sid_hashed = $1)
+            AND date >= $2
+            AND ($3::date IS NULL OR date <= $3)
+        "#,
+        sid_hashed,
+        season.starts,
+        season.ends,
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut res = HashMap::<String, Totals>::new();
 
     for record in records {
+        if !season.contains(record.sign_in.and_local().date_naive()) {
+            continue;
+        }
+
         let dt = record.sign_out.expect("unreachable") - record.sign_in;
-        let hours = dt.num_minutes() as f64 / 60.0;
-        res.add(record.hour_type, hours);
+        let policy = policies.get(&record.hour_type).copied().unwrap_or_default();
+
+        *res.entry(record.sid_hashed)
+            .or_default()
+            .entry(record.hour_type)
+            .or_default() += policy.credit(dt);
+    }
+
+    // adjustments are credited as-is, session policies don't apply
+    for adjustment in adjustments {
+        *res.entry(adjustment.sid_hashed)
+            .or_default()
+            .entry(adjustment.hour_type)
+            .or_default() += adjustment.hours;
+    }
+
+    Ok(res)
+}
+
+/// Totals of a single student in `season`, frozen if the season is archived
+/// and live otherwise
+pub(crate) async fn totals(
+    sid_hashed: &str,
+    season: &Season,
+    pg: &PgPool,
+) -> Result<Totals, sqlx::Error> {
+    if season.archived_at.is_some() {
+        return Ok(sqlx::query!(
+            r#"
+            SELECT hour_type AS "hour_type: HourType", hours
+            FROM season_totals
+            WHERE season_id = $1 AND sid_hashed = $2
+            "#,
+            season.id,
+            sid_hashed,
+        )
+        .fetch_all(pg)
+        .await?
+        .into_iter()
+        .map(|row| (row.hour_type, row.hours))
+        .collect());
+    }
+
+    let policies = SessionPolicy::all(pg).await?;
+    let mut conn = pg.acquire().await?;
+
+    Ok(tally(Some(sid_hashed), season, &policies, &mut *conn)
+        .await?
+        .remove(sid_hashed)
+        .unwrap_or_default())
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn route(
+    sid_hashed: String,
+    season: Option<String>,
+    pg: PgPool,
+) -> Result<Response, Error> {
+    let season = season::resolve(season.as_deref(), &pg)
+        .await?
+        .ok_or(Error::season())?;
+
+    let totals = totals(&sid_hashed, &season, &pg).await?;
+
+    let mut res = HourType::all(&pg)
+        .await?
+        .into_iter()
+        .map(|kind| (kind.to_string(), 0.0))
+        .collect::<Response>();
+
+    for (kind, hours) in totals {
+        *res.entry(kind.to_string()).or_default() += hours;
     }
 
     Ok(res)
@@ -1,6 +1,6 @@
-use crate::prelude::*;
+use crate::{cache::Cached, prelude::*};
 
-#[derive(Object, Debug)]
+#[derive(Object, Debug, Clone)]
 pub(super) struct StudentIdConfig {
     /// Length of the unhashed student ID.
     length: usize,
@@ -45,12 +45,20 @@ pub(super) async fn update(pg: &PgPool, new_config: StudentIdConfig) -> Result<(
 }
 
 pub(super) async fn query(pg: &PgPool) -> Result<StudentIdConfig, StudentIdError> {
-    let record = sqlx::query!(r#"SELECT length, regex FROM student_id_config"#)
-        .fetch_one(pg)
+    static CACHE: Cached<StudentIdConfig> = Cached::new();
+
+    let config = CACHE
+        .get(|| async {
+            let record = sqlx::query!(r#"SELECT length, regex FROM student_id_config"#)
+                .fetch_one(pg)
+                .await?;
+
+            Ok::<_, sqlx::Error>(StudentIdConfig {
+                length: record.length as usize,
+                regex: record.regex,
+            })
+        })
         .await?;
 
-    Ok(StudentIdConfig {
-        length: record.length as usize,
-        regex: record.regex,
-    })
+    Ok((*config).clone())
 }
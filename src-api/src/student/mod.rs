@@ -1,6 +1,6 @@
 mod add;
 mod delete;
-mod hours;
+pub(crate) mod hours;
 mod id;
 mod query;
 mod update;
@@ -8,7 +8,7 @@ mod update;
 use futures_util::stream::BoxStream;
 use poem_openapi::payload::EventStream;
 
-use crate::{dbstream::ReplicateStudent, prelude::*};
+use crate::{dbstream::ReplicateStudent, meeting::attendance, prelude::*};
 
 pub(crate) struct StudentService {
     pg: PgPool,
@@ -93,8 +93,27 @@ impl StudentService {
     }
 
     #[oai(path = "/:id_hashed/hours", method = "get")]
-    async fn hours(&self, id_hashed: Path<String>) -> Result<Json<hours::Response>, hours::Error> {
-        Ok(Json(hours::route(id_hashed.0, self.pg.clone()).await?))
+    async fn hours(
+        &self,
+        id_hashed: Path<String>,
+        /// Defaults to the current season
+        season: Query<Option<String>>,
+    ) -> Result<Json<hours::Response>, hours::Error> {
+        Ok(Json(
+            hours::route(id_hashed.0, season.0, self.pg.clone()).await?,
+        ))
+    }
+
+    #[oai(path = "/:id_hashed/attendance", method = "get")]
+    async fn attendance(
+        &self,
+        id_hashed: Path<String>,
+        /// Defaults to the current season
+        season: Query<Option<String>>,
+    ) -> Result<Json<attendance::StudentResponse>, attendance::StudentError> {
+        Ok(Json(
+            attendance::student(id_hashed.0, season.0, self.pg.clone()).await?,
+        ))
     }
 
     #[oai(path = "/config", method = "get")]
@@ -0,0 +1,13 @@
+use crate::{absence::Absence, prelude::*};
+
+#[derive(Clone, Debug, Serialize, Deserialize, Object)]
+pub(crate) struct AbsenceAdd {
+    pub(crate) admin_id: String,
+    #[serde(flatten)]
+    #[oai(flatten)]
+    pub(crate) absence: Absence,
+}
+
+migrator! {
+    AbsenceAdd {}
+}
@@ -0,0 +1,12 @@
+use crate::{adjustment::Adjustment, prelude::*};
+
+#[derive(Clone, Debug, Serialize, Deserialize, Object)]
+pub(crate) struct AdjustmentAdd {
+    pub(crate) admin_id: String,
+    /// Nested rather than flattened, since it has an `admin_id` of its own
+    pub(crate) adjustment: Adjustment,
+}
+
+migrator! {
+    AdjustmentAdd {}
+}
@@ -0,0 +1,14 @@
+use crate::{prelude::*, roster::group::GroupRecord};
+
+#[derive(Clone, Debug, Serialize, Deserialize, Object)]
+pub(crate) struct GroupSignIn {
+    pub(crate) admin_id: String,
+    pub(crate) hour_type: HourType,
+    /// When every student was signed in
+    pub(crate) at: chrono::DateTime<Utc>,
+    pub(crate) signed_in: Vec<GroupRecord>,
+}
+
+migrator! {
+    GroupSignIn {}
+}
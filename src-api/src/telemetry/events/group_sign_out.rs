@@ -0,0 +1,14 @@
+use crate::{prelude::*, roster::group::GroupRecord};
+
+#[derive(Clone, Debug, Serialize, Deserialize, Object)]
+pub(crate) struct GroupSignOut {
+    pub(crate) admin_id: String,
+    pub(crate) hour_type: HourType,
+    /// When every record was signed out
+    pub(crate) at: chrono::DateTime<Utc>,
+    pub(crate) signed_out: Vec<GroupRecord>,
+}
+
+migrator! {
+    GroupSignOut {}
+}
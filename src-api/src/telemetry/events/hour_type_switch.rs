@@ -0,0 +1,15 @@
+use crate::{prelude::*, roster::switch::Switched};
+
+#[derive(Clone, Debug, Serialize, Deserialize, Object)]
+pub(crate) struct HourTypeSwitch {
+    pub(crate) admin_id: String,
+    pub(crate) from: HourType,
+    pub(crate) to: HourType,
+    /// When every record was switched
+    pub(crate) at: chrono::DateTime<Utc>,
+    pub(crate) switched: Vec<Switched>,
+}
+
+migrator! {
+    HourTypeSwitch {}
+}
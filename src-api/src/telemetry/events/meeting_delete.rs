@@ -0,0 +1,13 @@
+use crate::{meeting::crud::Meeting, prelude::*};
+
+#[derive(Clone, Debug, Serialize, Deserialize, Object)]
+pub(crate) struct MeetingDelete {
+    pub(crate) admin_id: String,
+    #[serde(flatten)]
+    #[oai(flatten)]
+    pub(crate) meeting: Meeting,
+}
+
+migrator! {
+    MeetingDelete {}
+}
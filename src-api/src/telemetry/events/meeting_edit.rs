@@ -0,0 +1,15 @@
+use crate::{meeting::crud::Meeting, prelude::*};
+
+#[derive(Clone, Debug, Serialize, Deserialize, Object)]
+pub(crate) struct MeetingEdit {
+    pub(crate) admin_id: String,
+    pub(crate) old: Meeting,
+    /// The meeting after the edit
+    #[serde(flatten)]
+    #[oai(flatten)]
+    pub(crate) meeting: Meeting,
+}
+
+migrator! {
+    MeetingEdit {}
+}
@@ -0,0 +1,15 @@
+use crate::{dbstream::Record, prelude::*, roster::auto_close::AutoClosePolicy};
+
+#[derive(Clone, Debug, Serialize, Deserialize, Object)]
+pub(crate) struct RecordAutoClose {
+    /// The policy that was applied to close the record
+    pub(crate) policy: AutoClosePolicy,
+    /// The record after it was closed
+    #[serde(flatten)]
+    #[oai(flatten)]
+    pub(crate) record: Record,
+}
+
+migrator! {
+    RecordAutoClose {}
+}
@@ -0,0 +1,15 @@
+use crate::{dbstream::Record, prelude::*, roster::bulk::BulkOperation};
+
+#[derive(Clone, Debug, Serialize, Deserialize, Object)]
+pub(crate) struct RecordBulk {
+    pub(crate) admin_id: String,
+    /// Every record the operation was applied to
+    pub(crate) record_ids: Vec<String>,
+    pub(crate) operation: BulkOperation,
+    /// The records before the operation
+    pub(crate) old: Vec<Record>,
+}
+
+migrator! {
+    RecordBulk {}
+}
@@ -0,0 +1,12 @@
+use crate::{prelude::*, season::Season};
+
+#[derive(Clone, Debug, Serialize, Deserialize, Object)]
+pub(crate) struct SeasonRollover {
+    pub(crate) admin_id: String,
+    pub(crate) archived: Season,
+    pub(crate) current: Season,
+}
+
+migrator! {
+    SeasonRollover {}
+}
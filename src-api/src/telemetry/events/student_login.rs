@@ -1,10 +1,23 @@
-use crate::prelude::*;
+use crate::{prelude::*, roster::geofence::Location};
 
 #[derive(Clone, Debug, Serialize, Deserialize, Object)]
 pub(crate) struct StudentLogin {
     pub(crate) sid_hashed: String,
     pub(crate) record_id: String,
     pub(crate) admin_id: String,
+    /// Whether the swipe came from outside every geofence for the hour type
+    #[serde(default)]
+    pub(crate) flagged: bool,
+    /// Where the swipe came from, if the device reported it
+    #[serde(default)]
+    pub(crate) location: Option<Location>,
+    /// Set if the student swiped from their own enrolled device
+    #[serde(default)]
+    pub(crate) device_id: Option<String>,
+    /// Set if the swipe was taken by, or shown on, a registered kiosk. If
+    /// so, `admin_id` is the admin who registered it.
+    #[serde(default)]
+    pub(crate) kiosk_id: Option<String>,
 }
 
 migrator! {
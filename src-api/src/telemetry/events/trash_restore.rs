@@ -0,0 +1,12 @@
+use crate::{prelude::*, trash::TrashItem};
+
+#[derive(Clone, Debug, Serialize, Deserialize, Object)]
+pub(crate) struct TrashRestore {
+    pub(crate) admin_id: String,
+    /// The item as it was in the trash
+    pub(crate) item: TrashItem,
+}
+
+migrator! {
+    TrashRestore {}
+}
@@ -57,7 +57,6 @@ impl TelemetryService {
 
         let id = cuid2();
         let filter = request.0;
-        let pg = self.pg.clone();
 
         sqlx::query!(
             r#"
@@ -67,21 +66,9 @@ impl TelemetryService {
             id,
             serde_json::to_value(&filter)?,
         )
-        .execute(&pg)
+        .execute(&self.pg)
         .await?;
 
-        tokio::spawn(async move {
-            sqlx::query!(
-                r#"
-                DELETE FROM telemetry_streams
-                WHERE updated_at < (NOW() - INTERVAL '1 hour')
-                "#
-            )
-            .execute(&pg)
-            .await
-            .log();
-        });
-
         Ok(PlainText(id))
     }
 
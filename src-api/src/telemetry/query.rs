@@ -2,7 +2,13 @@ use itertools::Itertools;
 use poem_openapi::Union;
 use sqlx::AssertSqlSafe;
 
-use crate::{dbstream::Admin, prelude::*, telemetry::TelemetryEvent};
+use crate::{
+    absence::Absence,
+    adjustment::Adjustment,
+    dbstream::{Admin, Record},
+    prelude::*,
+    telemetry::TelemetryEvent,
+};
 
 const MAX_COUNT: usize = 100;
 const DEFAULT_COUNT: usize = 30;
@@ -83,6 +89,30 @@ impl StudentActionFilter {
 
         admin && sid
     }
+
+    fn match_absence(&self, admin_id: &str, absence: &Absence) -> bool {
+        self.matches(admin_id, &absence.sid_hashed)
+    }
+
+    fn match_adjustment(&self, admin_id: &str, adjustment: &Adjustment) -> bool {
+        self.matches(admin_id, &adjustment.sid_hashed)
+    }
+}
+
+#[derive(Serialize, Deserialize, Object, Clone, Debug)]
+pub(super) struct StudentIdFilter {
+    /// List of student hashed SIDs to filter by
+    sid_hashed: Vec<String>,
+}
+
+impl StudentIdFilter {
+    fn matches(&self, sid_hashed: &str) -> bool {
+        self.sid_hashed.contains(&sid_hashed.to_string()) || self.sid_hashed.is_empty()
+    }
+
+    fn match_record(&self, record: &Record) -> bool {
+        self.matches(&record.sid_hashed)
+    }
 }
 
 #[derive(Serialize, Deserialize, Union, Clone, Debug)]
@@ -101,11 +131,26 @@ pub(super) enum EventTypeFilter {
     RecordAdd(AdminIdFilter),
     RecordDelete(AdminIdFilter),
     RecordEdit(AdminIdFilter),
+    RecordBulk(AdminIdFilter),
+    RecordAutoClose(StudentIdFilter),
     StudentAdd(AdminIdFilter),
     StudentDelete(AdminIdFilter),
     StudentEdit(AdminIdFilter),
     StudentLogin(StudentActionFilter),
     StudentLogout(StudentActionFilter),
+    AbsenceAdd(StudentActionFilter),
+    AbsenceApprove(StudentActionFilter),
+    AbsenceDelete(StudentActionFilter),
+    AdjustmentAdd(StudentActionFilter),
+    AdjustmentDelete(StudentActionFilter),
+    HourTypeSwitch(AdminIdFilter),
+    SeasonRollover(AdminIdFilter),
+    TrashRestore(AdminIdFilter),
+    GroupSignIn(AdminIdFilter),
+    GroupSignOut(AdminIdFilter),
+    MeetingAdd(AdminIdFilter),
+    MeetingEdit(AdminIdFilter),
+    MeetingDelete(AdminIdFilter),
 }
 
 impl EventTypeFilter {
@@ -141,11 +186,26 @@ impl EventTypeFilter {
             RecordAdd { admin_id };
             RecordDelete { admin_id };
             RecordEdit { admin_id };
+            RecordBulk { admin_id };
+            RecordAutoClose { record } match_record;
             StudentAdd { admin_id };
             StudentDelete { admin_id };
             StudentEdit { admin_id };
             StudentLogin { admin_id, sid_hashed };
             StudentLogout { admin_id, sid_hashed };
+            AbsenceAdd { admin_id, absence } match_absence;
+            AbsenceApprove { admin_id, absence } match_absence;
+            AbsenceDelete { admin_id, absence } match_absence;
+            AdjustmentAdd { admin_id, adjustment } match_adjustment;
+            AdjustmentDelete { admin_id, adjustment } match_adjustment;
+            HourTypeSwitch { admin_id };
+            SeasonRollover { admin_id };
+            TrashRestore { admin_id };
+            GroupSignIn { admin_id };
+            GroupSignOut { admin_id };
+            MeetingAdd { admin_id };
+            MeetingEdit { admin_id };
+            MeetingDelete { admin_id };
         )
     }
 
@@ -202,11 +262,26 @@ impl EventTypeFilter {
             RecordAdd { admin_id };
             RecordDelete { admin_id };
             RecordEdit { admin_id };
+            RecordBulk { admin_id };
+            RecordAutoClose { sid_hashed };
             StudentAdd { admin_id };
             StudentDelete { admin_id };
             StudentEdit { admin_id };
             StudentLogin { admin_id, sid_hashed };
             StudentLogout { admin_id, sid_hashed };
+            AbsenceAdd { admin_id, sid_hashed };
+            AbsenceApprove { admin_id, sid_hashed };
+            AbsenceDelete { admin_id, sid_hashed };
+            AdjustmentAdd { admin_id, sid_hashed as adjustment.sid_hashed };
+            AdjustmentDelete { admin_id, sid_hashed as adjustment.sid_hashed };
+            HourTypeSwitch { admin_id };
+            SeasonRollover { admin_id };
+            TrashRestore { admin_id };
+            GroupSignIn { admin_id };
+            GroupSignOut { admin_id };
+            MeetingAdd { admin_id };
+            MeetingEdit { admin_id };
+            MeetingDelete { admin_id };
         )
     }
 }
@@ -0,0 +1,266 @@
+use itertools::Itertools;
+use sqlx::AssertSqlSafe;
+
+use super::purge::retention;
+use crate::{
+    dbstream::{Record, Student},
+    prelude::*,
+};
+
+/// Tables whose rows are deleted along with a student, and restored with them.
+///
+/// Badges and devices are left out on purpose: they are credentials, and may
+/// have been enrolled to someone else since.
+const STUDENT_DEPENDENTS: [&str; 4] = ["records", "absences", "hour_adjustments", "season_totals"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Enum, sqlx::Type)]
+#[oai(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "trash_kind", rename_all = "snake_case")]
+pub(crate) enum TrashKind {
+    Record,
+    Student,
+}
+
+/// A deleted row, kept until it is restored or purged
+#[derive(Object, Serialize, Deserialize, Clone, Debug, sqlx::FromRow)]
+pub(crate) struct TrashItem {
+    pub kind: TrashKind,
+    /// ID of the deleted row, which it keeps when restored
+    pub pkey: String,
+    /// The deleted row
+    pub data: serde_json::Value,
+    /// Rows deleted along with it, by table
+    pub dependents: serde_json::Value,
+    pub deleted_by: Option<String>,
+    pub deleted_at: chrono::DateTime<Utc>,
+}
+
+#[derive(Object)]
+#[oai(rename = "TrashQueryManyResponse")]
+pub(super) struct QueryManyResponse {
+    /// Most recently deleted first
+    items: Vec<TrashItem>,
+    /// Items are purged this many days after they are deleted
+    retention_days: i32,
+}
+
+#[derive(ApiResponse, ApiError)]
+#[from(JwtVerifyError, PermissionDeniedError)]
+pub(super) enum Error {
+    #[oai(status = 401)]
+    Unauthorized(PlainText<String>),
+
+    #[oai(status = 403)]
+    Forbidden(PlainText<String>),
+
+    /// Nothing with the given ID is in the trash
+    #[oai(status = 404)]
+    #[construct("Item not found in trash")]
+    NotFound(PlainText<String>),
+
+    /// The row was recreated since it was deleted, or something it depends on
+    /// (e.g. its student or hour type) no longer exists
+    #[oai(status = 409)]
+    #[construct("Item conflicts with existing data, or depends on something that no longer exists")]
+    Conflict(PlainText<String>),
+
+    #[oai(status = 500)]
+    #[from(sqlx::Error, "Database error")]
+    InternalServerError(PlainText<String>),
+}
+
+fn conflict(err: sqlx::Error) -> Error {
+    match &err {
+        // unique_violation, foreign_key_violation
+        sqlx::Error::Database(db) if matches!(db.code().as_deref(), Some("23505" | "23503")) => {
+            Error::conflict()
+        }
+        _ => err.into(),
+    }
+}
+
+/// Move records to the trash, returning them as they were
+pub(crate) async fn trash_records(
+    ids: &[String],
+    admin_id: &str,
+    pg: impl sqlx::PgExecutor<'_>,
+) -> Result<Vec<Record>, sqlx::Error> {
+    sqlx::query_as::<_, Record>(
+        r#"
+        WITH deleted AS (
+            DELETE FROM records
+            WHERE id = ANY($1)
+            RETURNING *
+        ), trashed AS (
+            INSERT INTO trash (kind, pkey, data, deleted_by)
+            SELECT 'record', deleted.id, to_jsonb(deleted), $2
+            FROM deleted
+            ON CONFLICT (kind, pkey) DO UPDATE
+            SET data = EXCLUDED.data,
+                dependents = EXCLUDED.dependents,
+                deleted_by = EXCLUDED.deleted_by,
+                deleted_at = EXCLUDED.deleted_at
+        )
+        SELECT * FROM deleted
+        "#,
+    )
+    .bind(ids)
+    .bind(admin_id)
+    .fetch_all(pg)
+    .await
+}
+
+/// Move a student to the trash, along with everything deleted with them
+pub(crate) async fn trash_student(
+    id_hashed: &str,
+    admin_id: &str,
+    pg: &PgPool,
+) -> Result<Option<Student>, sqlx::Error> {
+    let dependents = STUDENT_DEPENDENTS
+        .iter()
+        .map(|table| {
+            format!(
+                "'{table}', (SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM {table} t WHERE \
+                 t.sid_hashed = s.id_hashed)"
+            )
+        })
+        .join(", ");
+
+    sqlx::query_as::<_, Student>(AssertSqlSafe(format!(
+        r#"
+        WITH trashed AS (
+            INSERT INTO trash (kind, pkey, data, dependents, deleted_by)
+            SELECT 'student', s.id_hashed, to_jsonb(s), jsonb_build_object({dependents}), $2
+            FROM students s
+            WHERE s.id_hashed = $1
+            ON CONFLICT (kind, pkey) DO UPDATE
+            SET data = EXCLUDED.data,
+                dependents = EXCLUDED.dependents,
+                deleted_by = EXCLUDED.deleted_by,
+                deleted_at = EXCLUDED.deleted_at
+        )
+        DELETE FROM students
+        WHERE id_hashed = $1
+        RETURNING *
+        "#
+    )))
+    .bind(id_hashed)
+    .bind(admin_id)
+    .fetch_optional(pg)
+    .await
+}
+
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn query_many(kind: TrashKind, pg: PgPool) -> Result<QueryManyResponse, Error> {
+    let items = sqlx::query_as::<_, TrashItem>(
+        r#"
+        SELECT *
+        FROM trash
+        WHERE kind = $1
+        ORDER BY deleted_at DESC
+        "#,
+    )
+    .bind(kind)
+    .fetch_all(&pg)
+    .await?;
+
+    Ok(QueryManyResponse {
+        items,
+        retention_days: retention(),
+    })
+}
+
+/// Recreate a deleted row exactly as it was, and take it out of the trash
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn restore(
+    kind: TrashKind,
+    pkey: String,
+    claims: jwt::Claims,
+    pg: PgPool,
+) -> Result<TrashItem, Error> {
+    let mut tx = pg.begin().await?;
+
+    let item = sqlx::query_as::<_, TrashItem>(
+        r#"
+        DELETE FROM trash
+        WHERE kind = $1 AND pkey = $2
+        RETURNING *
+        "#,
+    )
+    .bind(kind)
+    .bind(&pkey)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(Error::not_found())?;
+
+    let table = match kind {
+        TrashKind::Record => "records",
+        TrashKind::Student => "students",
+    };
+
+    sqlx::query(AssertSqlSafe(format!(
+        "INSERT INTO {table} SELECT * FROM jsonb_populate_record(NULL::{table}, $1)"
+    )))
+    .bind(&item.data)
+    .execute(&mut *tx)
+    .await
+    .map_err(conflict)?;
+
+    if kind == TrashKind::Student {
+        for table in STUDENT_DEPENDENTS {
+            // totals of seasons deleted since can't be restored
+            let filter = if table == "season_totals" {
+                "WHERE season_id IN (SELECT id FROM seasons)"
+            } else {
+                ""
+            };
+
+            sqlx::query(AssertSqlSafe(format!(
+                r#"
+                INSERT INTO {table}
+                SELECT * FROM jsonb_populate_recordset(NULL::{table}, $1->'{table}')
+                {filter}
+                "#
+            )))
+            .bind(&item.dependents)
+            .execute(&mut *tx)
+            .await
+            .map_err(conflict)?;
+        }
+    }
+
+    tx.commit().await?;
+
+    let item_telemeter = item.clone();
+    tokio::spawn(async move {
+        telemeter(
+            TrashRestore {
+                admin_id: claims.sub,
+                item: item_telemeter,
+            },
+            &pg,
+        )
+        .await
+        .log();
+    });
+
+    Ok(item)
+}
+
+/// Permanently delete an item in the trash, ahead of its retention period
+#[tracing::instrument(skip(pg), err)]
+pub(super) async fn purge(kind: TrashKind, pkey: String, pg: PgPool) -> Result<TrashItem, Error> {
+    sqlx::query_as::<_, TrashItem>(
+        r#"
+        DELETE FROM trash
+        WHERE kind = $1 AND pkey = $2
+        RETURNING *
+        "#,
+    )
+    .bind(kind)
+    .bind(pkey)
+    .fetch_optional(&pg)
+    .await?
+    .ok_or(Error::not_found())
+}
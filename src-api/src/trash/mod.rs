@@ -0,0 +1,81 @@
+mod crud;
+pub(crate) mod purge;
+
+pub(crate) use crud::{TrashItem, TrashKind, trash_records, trash_student};
+
+use crate::prelude::*;
+
+pub(crate) struct TrashService {
+    pg: PgPool,
+}
+
+impl TrashService {
+    pub(crate) fn new(pg: PgPool) -> Self {
+        Self { pg }
+    }
+}
+
+impl TrashKind {
+    /// Permission needed to see items of this kind
+    fn view(self) -> Permission {
+        match self {
+            Self::Record => Permission::HoursView,
+            Self::Student => Permission::StudentView,
+        }
+    }
+
+    /// Permission needed to restore or purge items of this kind, the same as
+    /// deleting them
+    fn edit(self) -> Permission {
+        match self {
+            Self::Record => Permission::HoursEdit,
+            Self::Student => Permission::StudentDelete,
+        }
+    }
+}
+
+#[auto_operation_ids]
+#[OpenApi(tag = "Tag::Trash", prefix_path = "/trash")]
+impl TrashService {
+    #[oai(path = "/:kind", method = "get")]
+    async fn query_many(
+        &self,
+        kind: Path<TrashKind>,
+        jwt: Jwt,
+    ) -> Result<Json<crud::QueryManyResponse>, crud::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(kind.0.view())?;
+
+        Ok(Json(crud::query_many(kind.0, self.pg.clone()).await?))
+    }
+
+    /// Recreates a deleted record or student with its original ID. Students
+    /// come back with their records, absences and adjustments.
+    #[oai(path = "/:kind/:pkey/restore", method = "post")]
+    async fn restore(
+        &self,
+        kind: Path<TrashKind>,
+        pkey: Path<String>,
+        jwt: Jwt,
+    ) -> Result<Json<TrashItem>, crud::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(kind.0.edit())?;
+
+        Ok(Json(
+            crud::restore(kind.0, pkey.0, claims, self.pg.clone()).await?,
+        ))
+    }
+
+    #[oai(path = "/:kind/:pkey", method = "delete")]
+    async fn purge(
+        &self,
+        kind: Path<TrashKind>,
+        pkey: Path<String>,
+        jwt: Jwt,
+    ) -> Result<Json<TrashItem>, crud::Error> {
+        let claims = jwt.verify()?;
+        claims.perms.assert(kind.0.edit())?;
+
+        Ok(Json(crud::purge(kind.0, pkey.0, self.pg.clone()).await?))
+    }
+}
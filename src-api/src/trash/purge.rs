@@ -0,0 +1,34 @@
+use std::sync::LazyLock;
+
+use crate::prelude::*;
+
+/// Days an item is kept in the trash before it is purged
+pub(crate) fn retention() -> i32 {
+    static RETENTION: LazyLock<i32> = LazyLock::new(|| {
+        env::TRASH_RETENTION_DAYS
+            .parse()
+            .expect("TRASH_RETENTION_DAYS must be a number of days")
+    });
+
+    *RETENTION
+}
+
+/// Permanently delete every item older than the retention period
+pub(crate) async fn sweep(pg: &PgPool) -> Result<(), sqlx::Error> {
+    let purged = sqlx::query!(
+        r#"
+        DELETE FROM trash
+        WHERE deleted_at < NOW() - make_interval(days => $1)
+        "#,
+        retention(),
+    )
+    .execute(pg)
+    .await?
+    .rows_affected();
+
+    if purged > 0 {
+        info!(purged, "purged expired trash");
+    }
+
+    Ok(())
+}
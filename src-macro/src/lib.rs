@@ -259,11 +259,11 @@ async fn declare_replication_impl(table: String) -> proc_macro2::TokenStream {
         };
 
         let mut rust_ty = match col.data_type.as_deref() {
+            Some("text") if name == "hour_type" => "HourType",
             Some("text") => "String",
             Some("timestamp with time zone") => "::chrono::DateTime<::chrono::Utc>",
             Some("boolean") => "bool",
             Some("bytea") => continue, // encrypted data, don't touch
-            Some("USER-DEFINED") => "HourType",
             Some(other) => panic!("Unsupported data type: {other}"),
             None => continue,
         }